@@ -0,0 +1,26 @@
+// Render every page of the menu as the 128x32 display shows it, into PBM
+// images in the given directory (default `snapshots`, the golden images of
+// the tests)
+//
+// cargo run --example snapshots [dir]
+
+use clock_core::draw::{draw, file_name, samples, FrameBuffer};
+use std::path::PathBuf;
+
+fn main() {
+    let dir = PathBuf::from(std::env::args().nth(1).unwrap_or("snapshots".into()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    samples(|name, view| {
+        let mut frame = FrameBuffer::default();
+        draw(&view.screen(), &mut frame).unwrap();
+        let mut pbm = String::new();
+        frame.write_pbm(&mut pbm).unwrap();
+
+        let mut file = String::new();
+        file_name(name, &mut file).unwrap();
+        let path = dir.join(file);
+        std::fs::write(&path, pbm).unwrap();
+        println!("{:<12} {}", name, path.display());
+    });
+}
@@ -0,0 +1,278 @@
+//! Alarm state machine
+//!
+//! Tracks a ringing alarm from the moment it triggers until it is dismissed,
+//! either by the user or by timing out. Time is advanced by calling `tick`
+//! once per second.
+
+use heapless::String;
+
+/// Number of alarm slots
+pub const MAX_ALARMS: usize = 8;
+/// Maximum length of an alarm label
+pub const LABEL_LEN: usize = 16;
+/// Default snooze length in minutes
+pub const SNOOZE_MINUTES: u8 = 5;
+/// Default number of seconds an alarm rings before it is dismissed
+pub const RING_SECONDS: u32 = 60;
+
+/// An entry in the alarm table
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alarm {
+    pub hour: u8,
+    pub minute: u8,
+    pub enabled: bool,
+    /// Bit 0 is Monday, bit 6 is Sunday
+    pub weekdays: u8,
+    pub label: String<LABEL_LEN>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum State {
+    /// No alarm active
+    Idle,
+    /// Alarm `alarm` is ringing, `elapsed` seconds since it (re)started
+    Ringing {
+        alarm: usize,
+        snoozes: u8,
+        elapsed: u32,
+    },
+    /// Alarm `alarm` is snoozed, it rings again in `remaining` seconds
+    Snoozed {
+        alarm: usize,
+        snoozes: u8,
+        remaining: u32,
+    },
+}
+
+/// What the buzzer should do after a transition
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    None,
+    /// Start ringing
+    Ring,
+    /// Stop ringing
+    Silence,
+}
+
+#[derive(Debug)]
+pub struct AlarmState {
+    state: State,
+    snooze_minutes: u8,
+    ring_seconds: u32,
+}
+
+impl Default for AlarmState {
+    fn default() -> Self {
+        Self::new(SNOOZE_MINUTES, RING_SECONDS)
+    }
+}
+
+impl AlarmState {
+    pub fn new(snooze_minutes: u8, ring_seconds: u32) -> Self {
+        Self {
+            state: State::Idle,
+            snooze_minutes,
+            ring_seconds,
+        }
+    }
+
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    /// True while ringing or snoozed
+    pub fn is_active(&self) -> bool {
+        self.state != State::Idle
+    }
+
+    pub fn snooze_minutes(&self) -> u8 {
+        self.snooze_minutes
+    }
+
+    pub fn set_snooze_minutes(&mut self, minutes: u8) {
+        self.snooze_minutes = minutes;
+    }
+
+    /// Alarm `alarm` is due, a new alarm replaces an active one
+    pub fn trigger(&mut self, alarm: usize) -> Action {
+        self.state = State::Ringing {
+            alarm,
+            snoozes: 0,
+            elapsed: 0,
+        };
+        Action::Ring
+    }
+
+    /// Snooze a ringing alarm, ignored otherwise
+    pub fn snooze(&mut self) -> Action {
+        match self.state {
+            State::Ringing { alarm, snoozes, .. } => {
+                self.state = State::Snoozed {
+                    alarm,
+                    snoozes: snoozes.saturating_add(1),
+                    remaining: self.snooze_minutes as u32 * 60,
+                };
+                Action::Silence
+            }
+            _ => Action::None,
+        }
+    }
+
+    /// Dismiss a ringing or snoozed alarm
+    pub fn dismiss(&mut self) -> Action {
+        let action = match self.state {
+            State::Ringing { .. } => Action::Silence,
+            _ => Action::None,
+        };
+        self.state = State::Idle;
+        action
+    }
+
+    /// Advance one second
+    pub fn tick(&mut self) -> Action {
+        match self.state {
+            State::Idle => Action::None,
+            State::Ringing {
+                alarm,
+                snoozes,
+                elapsed,
+            } => {
+                if elapsed + 1 >= self.ring_seconds {
+                    self.state = State::Idle;
+                    Action::Silence
+                } else {
+                    self.state = State::Ringing {
+                        alarm,
+                        snoozes,
+                        elapsed: elapsed + 1,
+                    };
+                    Action::None
+                }
+            }
+            State::Snoozed {
+                alarm,
+                snoozes,
+                remaining,
+            } => {
+                if remaining <= 1 {
+                    self.state = State::Ringing {
+                        alarm,
+                        snoozes,
+                        elapsed: 0,
+                    };
+                    Action::Ring
+                } else {
+                    self.state = State::Snoozed {
+                        alarm,
+                        snoozes,
+                        remaining: remaining - 1,
+                    };
+                    Action::None
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_alarm {
+    use super::*;
+
+    #[test]
+    fn test_trigger() {
+        let mut alarm = AlarmState::default();
+        assert_eq!(alarm.tick(), Action::None);
+        assert_eq!(alarm.trigger(3), Action::Ring);
+        assert_eq!(
+            alarm.state(),
+            State::Ringing {
+                alarm: 3,
+                snoozes: 0,
+                elapsed: 0
+            }
+        );
+    }
+
+    #[test]
+    fn test_dismiss() {
+        let mut alarm = AlarmState::default();
+        alarm.trigger(0);
+        assert_eq!(alarm.dismiss(), Action::Silence);
+        assert_eq!(alarm.state(), State::Idle);
+        assert_eq!(alarm.dismiss(), Action::None);
+    }
+
+    #[test]
+    fn test_snooze() {
+        let mut alarm = AlarmState::new(1, RING_SECONDS);
+        assert_eq!(alarm.snooze(), Action::None);
+        alarm.trigger(0);
+        assert_eq!(alarm.snooze(), Action::Silence);
+        assert_eq!(
+            alarm.state(),
+            State::Snoozed {
+                alarm: 0,
+                snoozes: 1,
+                remaining: 60
+            }
+        );
+        // snoozing again while snoozed is ignored
+        assert_eq!(alarm.snooze(), Action::None);
+        for _ in 0..59 {
+            assert_eq!(alarm.tick(), Action::None);
+        }
+        assert_eq!(alarm.tick(), Action::Ring);
+        assert_eq!(
+            alarm.state(),
+            State::Ringing {
+                alarm: 0,
+                snoozes: 1,
+                elapsed: 0
+            }
+        );
+        alarm.snooze();
+        assert_eq!(
+            alarm.state(),
+            State::Snoozed {
+                alarm: 0,
+                snoozes: 2,
+                remaining: 60
+            }
+        );
+    }
+
+    #[test]
+    fn test_dismiss_snoozed() {
+        let mut alarm = AlarmState::default();
+        alarm.trigger(0);
+        alarm.snooze();
+        assert_eq!(alarm.dismiss(), Action::None);
+        assert!(!alarm.is_active());
+    }
+
+    #[test]
+    fn test_ring_timeout() {
+        let mut alarm = AlarmState::new(SNOOZE_MINUTES, 3);
+        alarm.trigger(0);
+        assert_eq!(alarm.tick(), Action::None);
+        assert_eq!(alarm.tick(), Action::None);
+        assert_eq!(alarm.tick(), Action::Silence);
+        assert_eq!(alarm.state(), State::Idle);
+    }
+
+    #[test]
+    fn test_retrigger() {
+        let mut alarm = AlarmState::default();
+        alarm.trigger(0);
+        alarm.snooze();
+        assert_eq!(alarm.trigger(1), Action::Ring);
+        assert_eq!(
+            alarm.state(),
+            State::Ringing {
+                alarm: 1,
+                snoozes: 0,
+                elapsed: 0
+            }
+        );
+    }
+}
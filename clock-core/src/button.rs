@@ -0,0 +1,353 @@
+//! Button state machine
+//!
+//! Turns raw samples of a button into debounced events. The caller passes
+//! the sampled level and a millisecond timestamp to `update`, either
+//! periodically or on edges, and calls again after `next_update` while a
+//! timed event is pending. Timestamps wrap around.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Event {
+    Press,
+    Release,
+    /// Held down for `Timings::long_press_ms`, sent once per press
+    LongPress,
+    /// Pressed again within `Timings::double_click_ms` of a short click,
+    /// sent instead of `Press`
+    DoubleClick,
+    /// Auto-repeat after `LongPress`, every `Timings::repeat_ms`
+    Held,
+    /// A short click, neither long nor followed by a double click, sent
+    /// `Timings::double_click_ms` after its `Release`
+    Click,
+}
+
+/// Timings in milliseconds
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Timings {
+    /// The level must be stable this long to be accepted
+    pub debounce_ms: u32,
+    pub long_press_ms: u32,
+    pub double_click_ms: u32,
+    /// 0 disables auto-repeat
+    pub repeat_ms: u32,
+}
+
+impl Default for Timings {
+    fn default() -> Self {
+        Self {
+            debounce_ms: 20,
+            long_press_ms: 1000,
+            double_click_ms: 300,
+            repeat_ms: 200,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Button {
+    timings: Timings,
+    /// Last sampled level and when it changed
+    raw: bool,
+    raw_since: u32,
+    /// Debounced level
+    pressed: bool,
+    pressed_at: u32,
+    long: bool,
+    next_repeat: u32,
+    /// Release of a short click, a press soon after is a double click
+    clicked_at: Option<u32>,
+    /// The current press is the second of a double click
+    double: bool,
+}
+
+impl Button {
+    pub fn new(timings: Timings) -> Self {
+        Self {
+            timings,
+            ..Default::default()
+        }
+    }
+
+    /// Debounced level
+    pub fn is_pressed(&self) -> bool {
+        self.pressed
+    }
+
+    /// Feed a sample taken at `now`
+    ///
+    /// # Examples
+    /// ```
+    /// use clock_core::button::*;
+    /// let mut button = Button::default();
+    /// assert_eq!(button.update(true, 0), None);
+    /// assert_eq!(button.update(true, 20), Some(Event::Press));
+    /// assert_eq!(button.update(true, 990), None);
+    /// assert_eq!(button.update(true, 1000), Some(Event::LongPress));
+    /// assert_eq!(button.update(false, 1100), None);
+    /// assert_eq!(button.update(false, 1120), Some(Event::Release));
+    /// assert_eq!(button.next_update(1120), None);
+    /// ```
+    pub fn update(&mut self, pressed: bool, now: u32) -> Option<Event> {
+        if pressed != self.raw {
+            self.raw = pressed;
+            self.raw_since = now;
+        }
+
+        if self.raw != self.pressed {
+            if now.wrapping_sub(self.raw_since) < self.timings.debounce_ms {
+                return None;
+            }
+            // events are dated from the first edge
+            let at = self.raw_since;
+            self.pressed = self.raw;
+            return Some(if self.pressed {
+                self.pressed_at = at;
+                self.long = false;
+                let double = self
+                    .clicked_at
+                    .take()
+                    .is_some_and(|t| at.wrapping_sub(t) <= self.timings.double_click_ms);
+                self.double = double;
+                if double {
+                    Event::DoubleClick
+                } else {
+                    Event::Press
+                }
+            } else {
+                if !self.long && !self.double {
+                    self.clicked_at = Some(at);
+                }
+                Event::Release
+            });
+        }
+
+        if !self.pressed {
+            let t = self.clicked_at?;
+            (now.wrapping_sub(t) > self.timings.double_click_ms).then(|| {
+                self.clicked_at = None;
+                Event::Click
+            })
+        } else if !self.long {
+            let held = now.wrapping_sub(self.pressed_at);
+            (held >= self.timings.long_press_ms).then(|| {
+                self.long = true;
+                self.next_repeat = self
+                    .pressed_at
+                    .wrapping_add(self.timings.long_press_ms + self.timings.repeat_ms);
+                Event::LongPress
+            })
+        } else {
+            let due = (now.wrapping_sub(self.next_repeat) as i32) >= 0;
+            (self.timings.repeat_ms > 0 && due).then(|| {
+                self.next_repeat = self.next_repeat.wrapping_add(self.timings.repeat_ms);
+                Event::Held
+            })
+        }
+    }
+
+    /// Milliseconds from `now` until `update` must be called again, even
+    /// without an edge, `None` if nothing is pending
+    pub fn next_update(&self, now: u32) -> Option<u32> {
+        let deadline = if self.raw != self.pressed {
+            self.raw_since.wrapping_add(self.timings.debounce_ms)
+        } else if !self.pressed {
+            // a click turns out single
+            self.clicked_at?
+                .wrapping_add(self.timings.double_click_ms + 1)
+        } else if !self.long {
+            self.pressed_at.wrapping_add(self.timings.long_press_ms)
+        } else if self.timings.repeat_ms > 0 {
+            self.next_repeat
+        } else {
+            return None;
+        };
+        Some((deadline.wrapping_sub(now) as i32).max(0) as u32)
+    }
+}
+
+#[cfg(test)]
+mod test_button {
+    use super::*;
+
+    /// Sample `pattern` every 10 ms, `#` is pressed and anything else is
+    /// released, returns the events with their sample times
+    fn events(button: &mut Button, start: u32, pattern: &str) -> Vec<(u32, Event)> {
+        pattern
+            .chars()
+            .enumerate()
+            .filter_map(|(i, c)| {
+                let now = start.wrapping_add(i as u32 * 10);
+                button.update(c == '#', now).map(|e| (now, e))
+            })
+            .collect()
+    }
+
+    fn timings() -> Timings {
+        Timings {
+            debounce_ms: 20,
+            long_press_ms: 100,
+            double_click_ms: 50,
+            repeat_ms: 30,
+        }
+    }
+
+    #[test]
+    fn test_click() {
+        let mut button = Button::new(timings());
+        assert_eq!(
+            events(&mut button, 0, "__#####____"),
+            [(40, Event::Press), (90, Event::Release)]
+        );
+    }
+
+    #[test]
+    fn test_bounce() {
+        let mut button = Button::new(timings());
+        // short glitches are ignored, the press is dated from the
+        // last edge
+        assert_eq!(
+            events(&mut button, 0, "_#_#_#_####_#_____"),
+            [(90, Event::Press), (150, Event::Release)]
+        );
+        assert!(!button.is_pressed());
+    }
+
+    #[test]
+    fn test_long_press_and_repeat() {
+        let mut button = Button::new(timings());
+        assert_eq!(
+            events(&mut button, 0, "###################___"),
+            [
+                (20, Event::Press),
+                (100, Event::LongPress),
+                (130, Event::Held),
+                (160, Event::Held),
+                (210, Event::Release),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_no_repeat() {
+        let mut button = Button::new(Timings {
+            repeat_ms: 0,
+            ..timings()
+        });
+        assert_eq!(
+            events(&mut button, 0, "###################"),
+            [(20, Event::Press), (100, Event::LongPress)]
+        );
+        assert_eq!(button.next_update(180), None);
+    }
+
+    #[test]
+    fn test_double_click() {
+        let mut button = Button::new(timings());
+        assert_eq!(
+            events(&mut button, 0, "####___####___####___"),
+            [
+                (20, Event::Press),
+                (60, Event::Release),
+                (90, Event::DoubleClick),
+                (130, Event::Release),
+                // a third click starts over
+                (160, Event::Press),
+                (200, Event::Release),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_slow_second_click() {
+        let mut button = Button::new(timings());
+        assert_eq!(
+            events(&mut button, 0, "####_______####___"),
+            [
+                (20, Event::Press),
+                (60, Event::Release),
+                (100, Event::Click),
+                (130, Event::Press),
+                (170, Event::Release),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_no_double_click_after_long_press() {
+        let mut button = Button::new(timings());
+        let events = events(&mut button, 0, "############___####___");
+        assert_eq!(events.last(), Some(&(210, Event::Release)));
+        assert_eq!(events[events.len() - 2], (170, Event::Press));
+    }
+
+    #[test]
+    fn test_click_only_when_single() {
+        // a click, once no second click can follow
+        let mut button = Button::new(timings());
+        assert_eq!(
+            events(&mut button, 0, "####________"),
+            [
+                (20, Event::Press),
+                (60, Event::Release),
+                (100, Event::Click)
+            ]
+        );
+        // a long press is not a click
+        let mut button = Button::new(timings());
+        let long = events(&mut button, 0, "############________");
+        assert!(!long.iter().any(|(_, event)| *event == Event::Click));
+        // nor a double click, or either of its clicks
+        let mut button = Button::new(timings());
+        assert_eq!(
+            events(&mut button, 0, "####___####________"),
+            [
+                (20, Event::Press),
+                (60, Event::Release),
+                (90, Event::DoubleClick),
+                (130, Event::Release),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_next_update() {
+        let mut button = Button::new(timings());
+        assert_eq!(button.next_update(0), None);
+        button.update(true, 0);
+        // debouncing
+        assert_eq!(button.next_update(5), Some(15));
+        assert_eq!(button.update(true, 20), Some(Event::Press));
+        // waiting for the long press
+        assert_eq!(button.next_update(20), Some(80));
+        assert_eq!(button.next_update(200), Some(0));
+        assert_eq!(button.update(true, 100), Some(Event::LongPress));
+        // waiting for the repeat
+        assert_eq!(button.next_update(100), Some(30));
+        button.update(false, 110);
+        assert_eq!(button.next_update(110), Some(20));
+        assert_eq!(button.update(false, 130), Some(Event::Release));
+        assert_eq!(button.next_update(130), None);
+        // a short click, waiting for a second one
+        button.update(true, 200);
+        assert_eq!(button.update(true, 220), Some(Event::Press));
+        button.update(false, 240);
+        assert_eq!(button.update(false, 260), Some(Event::Release));
+        assert_eq!(button.next_update(260), Some(31));
+        assert_eq!(button.update(false, 291), Some(Event::Click));
+        assert_eq!(button.next_update(291), None);
+    }
+
+    #[test]
+    fn test_wrapping_time() {
+        let mut button = Button::new(timings());
+        let start = u32::MAX - 55;
+        assert_eq!(
+            events(&mut button, start, "__###########_____"),
+            [
+                (start.wrapping_add(40), Event::Press),
+                (start.wrapping_add(120), Event::LongPress),
+                (start.wrapping_add(150), Event::Release),
+            ]
+        );
+    }
+}
@@ -0,0 +1,302 @@
+//! Calendar
+//!
+//! Gregorian date and time of day, with conversion to and from Unix epoch
+//! seconds. Seconds are kept in a `u32`, covering 1970-01-01 up to
+//! 2106-02-07.
+
+use core::fmt;
+
+/// Days from 0000-03-01 to 1970-01-01
+const EPOCH_SHIFT: u32 = 719_468;
+/// Days in a 400 year era
+const ERA_DAYS: u32 = 146_097;
+const SECONDS_PER_DAY: u32 = 24 * 3600;
+/// Last year of the epoch seconds, up to February 7th
+pub const MAX_YEAR: u16 = 2106;
+
+/// Leap years are divisible by 4, except centuries not divisible by 400
+///
+/// # Examples
+/// ```
+/// use clock_core::calendar::*;
+/// assert!(is_leap_year(2024));
+/// assert!(!is_leap_year(2100));
+/// assert!(is_leap_year(2000));
+/// ```
+pub fn is_leap_year(year: u16) -> bool {
+    (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400)
+}
+
+/// Number of days in `month` (1..=12) of `year`
+pub fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl Weekday {
+    const ALL: [Weekday; 7] = [
+        Weekday::Monday,
+        Weekday::Tuesday,
+        Weekday::Wednesday,
+        Weekday::Thursday,
+        Weekday::Friday,
+        Weekday::Saturday,
+        Weekday::Sunday,
+    ];
+
+    /// Days since Monday, matches bit positions of alarm weekday masks
+    pub fn index(self) -> u8 {
+        self as u8
+    }
+
+    pub fn name(self) -> &'static str {
+        ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"][self as usize]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DateTime {
+    pub year: u16,
+    /// 1..=12
+    pub month: u8,
+    /// 1..=31
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl DateTime {
+    /// Returns `None` if any field is out of range, or outside 1970-01-01
+    /// to 2106-02-07 06:28:15, the range of the epoch seconds
+    ///
+    /// # Examples
+    /// ```
+    /// use clock_core::calendar::*;
+    /// assert!(DateTime::new(2024, 2, 29, 12, 0, 0).is_some());
+    /// assert!(DateTime::new(2100, 2, 29, 12, 0, 0).is_none());
+    /// assert!(DateTime::new(2200, 1, 1, 0, 0, 0).is_none());
+    /// ```
+    pub fn new(year: u16, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> Option<Self> {
+        let datetime = Self {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+        };
+        let valid = (1970..=MAX_YEAR).contains(&year)
+            && (1..=12).contains(&month)
+            && day >= 1
+            && day <= days_in_month(year, month)
+            && hour < 24
+            && minute < 60
+            && second < 60
+            // days() does not underflow from 1970 on
+            && datetime.days() as u64 * SECONDS_PER_DAY as u64 + datetime.seconds_of_day() as u64
+                <= u32::MAX as u64;
+        valid.then_some(datetime)
+    }
+
+    /// Convert Unix epoch seconds to a date and time
+    ///
+    /// # Examples
+    /// ```
+    /// use clock_core::calendar::*;
+    /// assert_eq!(
+    ///     DateTime::from_epoch(0),
+    ///     DateTime::new(1970, 1, 1, 0, 0, 0).unwrap()
+    /// );
+    /// ```
+    pub fn from_epoch(seconds: u32) -> Self {
+        let days = seconds / SECONDS_PER_DAY;
+        let time = seconds % SECONDS_PER_DAY;
+
+        // civil from days, with years starting at March 1st
+        let days = days + EPOCH_SHIFT;
+        let era = days / ERA_DAYS;
+        let doe = days - era * ERA_DAYS;
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + (month <= 2) as u32;
+
+        Self {
+            year: year as u16,
+            month: month as u8,
+            day: day as u8,
+            hour: (time / 3600) as u8,
+            minute: (time / 60 % 60) as u8,
+            second: (time % 60) as u8,
+        }
+    }
+
+    /// Convert to Unix epoch seconds, the fields must be in the range of
+    /// `new`
+    ///
+    /// # Examples
+    /// ```
+    /// use clock_core::calendar::*;
+    /// let dt = DateTime::new(2026, 10, 18, 7, 30, 0).unwrap();
+    /// assert_eq!(dt.to_epoch(), 1_792_308_600);
+    /// ```
+    pub fn to_epoch(&self) -> u32 {
+        self.days() * SECONDS_PER_DAY + self.seconds_of_day()
+    }
+
+    /// Days since 1970-01-01
+    fn days(&self) -> u32 {
+        let month = self.month as u32;
+        let year = self.year as u32 - (month <= 2) as u32;
+        let era = year / 400;
+        let yoe = year - era * 400;
+        let mp = if month > 2 { month - 3 } else { month + 9 };
+        let doy = (153 * mp + 2) / 5 + self.day as u32 - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        era * ERA_DAYS + doe - EPOCH_SHIFT
+    }
+
+    pub fn seconds_of_day(&self) -> u32 {
+        self.hour as u32 * 3600 + self.minute as u32 * 60 + self.second as u32
+    }
+
+    pub fn weekday(&self) -> Weekday {
+        // 1970-01-01 was a Thursday
+        Weekday::ALL[((self.days() + 3) % 7) as usize]
+    }
+}
+
+/// Formats as `yyyy-mm-dd hh:mm:ss`
+impl fmt::Display for DateTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+#[cfg(test)]
+mod test_calendar {
+    use super::*;
+
+    fn dt(year: u16, month: u8, day: u8) -> DateTime {
+        DateTime::new(year, month, day, 0, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_leap_years() {
+        assert!(is_leap_year(1972));
+        assert!(is_leap_year(2000));
+        assert!(!is_leap_year(1900));
+        assert!(!is_leap_year(2026));
+        assert!(!is_leap_year(2100));
+        assert_eq!(days_in_month(2024, 2), 29);
+        assert_eq!(days_in_month(2026, 2), 28);
+        assert_eq!(days_in_month(2100, 2), 28);
+    }
+
+    #[test]
+    fn test_new_range() {
+        assert!(DateTime::new(1969, 12, 31, 0, 0, 0).is_none());
+        assert!(DateTime::new(2026, 0, 1, 0, 0, 0).is_none());
+        assert!(DateTime::new(2026, 13, 1, 0, 0, 0).is_none());
+        assert!(DateTime::new(2026, 4, 31, 0, 0, 0).is_none());
+        assert!(DateTime::new(2026, 4, 0, 0, 0, 0).is_none());
+        assert!(DateTime::new(2026, 4, 30, 24, 0, 0).is_none());
+        assert!(DateTime::new(2026, 4, 30, 23, 60, 0).is_none());
+        assert!(DateTime::new(2026, 4, 30, 23, 59, 60).is_none());
+        assert!(DateTime::new(2106, 2, 7, 6, 28, 15).is_some());
+        assert!(DateTime::new(2106, 2, 7, 6, 28, 16).is_none());
+        assert!(DateTime::new(2106, 2, 8, 0, 0, 0).is_none());
+        assert!(DateTime::new(2200, 1, 1, 0, 0, 0).is_none());
+        assert!(DateTime::new(u16::MAX, 12, 31, 23, 59, 59).is_none());
+    }
+
+    #[test]
+    fn test_epoch_known() {
+        assert_eq!(dt(1970, 1, 1).to_epoch(), 0);
+        assert_eq!(dt(2000, 3, 1).to_epoch(), 951_868_800);
+        assert_eq!(dt(2024, 2, 29).to_epoch(), 1_709_164_800);
+        assert_eq!(dt(2100, 3, 1).to_epoch(), 4_107_542_400);
+        assert_eq!(
+            DateTime::from_epoch(u32::MAX),
+            DateTime::new(2106, 2, 7, 6, 28, 15).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_month_boundaries() {
+        let cases = [
+            (dt(2026, 1, 31), dt(2026, 2, 1)),
+            (dt(2026, 2, 28), dt(2026, 3, 1)),
+            (dt(2024, 2, 28), dt(2024, 2, 29)),
+            (dt(2024, 2, 29), dt(2024, 3, 1)),
+            (dt(2026, 4, 30), dt(2026, 5, 1)),
+            (dt(2026, 12, 31), dt(2027, 1, 1)),
+            (dt(2000, 2, 28), dt(2000, 2, 29)),
+            (dt(2100, 2, 28), dt(2100, 3, 1)),
+        ];
+        for (last, first) in cases {
+            let end_of_day = last.to_epoch() + SECONDS_PER_DAY - 1;
+            let mut expected = last;
+            expected.hour = 23;
+            expected.minute = 59;
+            expected.second = 59;
+            assert_eq!(DateTime::from_epoch(end_of_day), expected);
+            assert_eq!(DateTime::from_epoch(end_of_day + 1), first);
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        // every day from 1970 through 2105, at varying times of day
+        let mut seconds = 0u32;
+        while let Some(next) = seconds.checked_add(SECONDS_PER_DAY + 3601) {
+            let dt = DateTime::from_epoch(seconds);
+            assert!(
+                DateTime::new(dt.year, dt.month, dt.day, dt.hour, dt.minute, dt.second).is_some()
+            );
+            assert_eq!(dt.to_epoch(), seconds);
+            seconds = next;
+        }
+    }
+
+    #[test]
+    fn test_weekday() {
+        assert_eq!(dt(1970, 1, 1).weekday(), Weekday::Thursday);
+        assert_eq!(dt(2000, 1, 1).weekday(), Weekday::Saturday);
+        assert_eq!(dt(2026, 10, 18).weekday(), Weekday::Sunday);
+        assert_eq!(dt(2026, 10, 19).weekday(), Weekday::Monday);
+        assert_eq!(dt(2100, 3, 1).weekday(), Weekday::Monday);
+        assert_eq!(Weekday::Sunday.index(), 6);
+    }
+
+    #[test]
+    fn test_display() {
+        let dt = DateTime::new(2026, 1, 2, 3, 4, 5).unwrap();
+        let mut buf = String::new();
+        use std::fmt::Write;
+        write!(buf, "{}", dt).unwrap();
+        assert_eq!(buf, "2026-01-02 03:04:05");
+    }
+}
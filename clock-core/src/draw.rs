@@ -0,0 +1,322 @@
+//! Screen drawing
+//!
+//! Draws a `view::Screen` on any `DrawTarget` of `BinaryColor`, the SSD1306
+//! on target and a `FrameBuffer` on the host, which writes PBM images for
+//! the snapshot tests.
+
+use core::fmt::{self, Write};
+
+use embedded_graphics::{
+    mono_font::{
+        ascii::{FONT_10X20, FONT_6X10},
+        MonoTextStyle,
+    },
+    pixelcolor::BinaryColor,
+    prelude::*,
+    primitives::{Line, PrimitiveStyle},
+    text::Text,
+};
+
+use crate::alarm::State;
+use crate::history::{HISTORY_LEN, HISTORY_PERIOD};
+use crate::menu::{Input, Kind, Page, ITEMS};
+use crate::sensor::SensorError;
+use crate::units::TemperatureUnit;
+use crate::view::{Graph, Screen, View};
+
+/// Display size in pixels
+pub const WIDTH: u32 = 128;
+pub const HEIGHT: u32 = 32;
+
+/// Width of the graph labels
+const LABEL_WIDTH: i32 = 6 * 6;
+
+/// Draw `screen`, the body in the large font, centered without a title or
+/// left aligned below the title in the small font, with the edited field
+/// underlined. The target is not cleared.
+pub fn draw<D>(screen: &Screen, target: &mut D) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    if let Some(graph) = &screen.graph {
+        return draw_graph(graph, target);
+    }
+    let font = &FONT_10X20;
+    let width = font.character_size.width as i32;
+    let origin = if screen.title.is_empty() {
+        let len = screen.body.chars().count() as i32;
+        Point::new(((WIDTH as i32 - len * width) / 2).max(0), 20)
+    } else {
+        let style = MonoTextStyle::new(&FONT_6X10, BinaryColor::On);
+        Text::new(&screen.title, Point::new(0, 8), style).draw(target)?;
+        Point::new(0, 27)
+    };
+    let style = MonoTextStyle::new(font, BinaryColor::On);
+    Text::new(&screen.body, origin, style).draw(target)?;
+    if let Some(range) = &screen.underline {
+        let y = origin.y + 4;
+        Line::new(
+            Point::new(origin.x + range.start as i32 * width, y),
+            Point::new(origin.x + range.end as i32 * width - 1, y),
+        )
+        .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+        .draw(target)?;
+    }
+    Ok(())
+}
+
+/// Draw the max and min labels at the top and bottom left, and the points
+/// as a line scaled between them to the right, the newest at the right edge
+fn draw_graph<D>(graph: &Graph, target: &mut D) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let style = MonoTextStyle::new(&FONT_6X10, BinaryColor::On);
+    Text::new(&graph.max_label, Point::new(0, 7), style).draw(target)?;
+    Text::new(&graph.min_label, Point::new(0, HEIGHT as i32 - 3), style).draw(target)?;
+
+    let left = LABEL_WIDTH + 2;
+    let width = WIDTH as i32 - 1 - left;
+    let bottom = HEIGHT as i32 - 1;
+    let range = graph.max - graph.min;
+    let len = graph.points.len();
+    let point = |i: usize, p: f32| {
+        let x = left + width - ((len - 1 - i) as i32 * width) / (HISTORY_LEN as i32 - 1);
+        let y = if range > 0.0 {
+            bottom - ((p - graph.min) / range * bottom as f32 + 0.5) as i32
+        } else {
+            bottom / 2
+        };
+        Point::new(x, y)
+    };
+    let line = PrimitiveStyle::with_stroke(BinaryColor::On, 1);
+    let mut points = graph.points.iter().enumerate().map(|(i, &p)| point(i, p));
+    if let Some(mut last) = points.next() {
+        Pixel(last, BinaryColor::On).draw(target)?;
+        for next in points {
+            Line::new(last, next).into_styled(line).draw(target)?;
+            last = next;
+        }
+    }
+    Ok(())
+}
+
+/// A display sized monochrome frame
+#[derive(Clone, PartialEq)]
+pub struct FrameBuffer {
+    /// Row major, one bit per pixel, MSB first
+    pixels: [u8; (WIDTH * HEIGHT / 8) as usize],
+}
+
+impl Default for FrameBuffer {
+    fn default() -> Self {
+        Self {
+            pixels: [0; (WIDTH * HEIGHT / 8) as usize],
+        }
+    }
+}
+
+impl FrameBuffer {
+    pub fn pixel(&self, x: u32, y: u32) -> bool {
+        let i = (y * WIDTH + x) as usize;
+        self.pixels[i / 8] & (0x80 >> (i % 8)) != 0
+    }
+
+    /// Write as a plain (ASCII) PBM image, one line per row
+    ///
+    /// # Examples
+    /// ```
+    /// use clock_core::draw::*;
+    /// let mut pbm = String::new();
+    /// FrameBuffer::default().write_pbm(&mut pbm).unwrap();
+    /// assert!(pbm.starts_with("P1\n128 32\n0000"));
+    /// ```
+    pub fn write_pbm(&self, w: &mut impl Write) -> fmt::Result {
+        writeln!(w, "P1\n{} {}", WIDTH, HEIGHT)?;
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                w.write_char(if self.pixel(x, y) { '1' } else { '0' })?;
+            }
+            w.write_char('\n')?;
+        }
+        Ok(())
+    }
+}
+
+impl OriginDimensions for FrameBuffer {
+    fn size(&self) -> Size {
+        Size::new(WIDTH, HEIGHT)
+    }
+}
+
+impl DrawTarget for FrameBuffer {
+    type Color = BinaryColor;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            if let (Ok(x @ 0..WIDTH), Ok(y @ 0..HEIGHT)) =
+                (u32::try_from(point.x), u32::try_from(point.y))
+            {
+                let i = (y * WIDTH + x) as usize;
+                match color {
+                    BinaryColor::On => self.pixels[i / 8] |= 0x80 >> (i % 8),
+                    BinaryColor::Off => self.pixels[i / 8] &= !(0x80 >> (i % 8)),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Call `f` with a name and a view for every page of the menu, with sample
+/// values, followed by an edit in progress, a sensor fault and the alarm
+/// screens
+pub fn samples(mut f: impl FnMut(&str, &View)) {
+    let mut view = View::default();
+    view.set_time(12, 30, 45);
+    view.set_unit(TemperatureUnit::Celsius);
+    // 90 minutes of history, warming from 19 to 23 °C in an hour and
+    // cooling since
+    for minute in 0..90i32 {
+        let temp = 23.0 - (60 - minute).abs() as f32 / 15.0;
+        for _ in 0..HISTORY_PERIOD {
+            view.set_temperature(temp);
+        }
+    }
+    view.set_temperature(21.5);
+
+    // walk the tree, entering the submenus
+    for item in ITEMS.iter() {
+        while view.menu().page() != item.page {
+            let input = match view.menu().item().kind {
+                Kind::Submenu => Input::Select,
+                _ => Input::Next,
+            };
+            view.input(input, &[0; 3]);
+        }
+        let values = match item.page {
+            Page::Alarms => [2, 0, 0],
+            Page::Time => [12, 30, 45],
+            Page::Date => [2026, 10, 18],
+            Page::Brightness => [50, 0, 0],
+            _ => [0; 3],
+        };
+        view.set_values(&values);
+        f(item.title, &view);
+    }
+
+    while view.menu().page() != Page::Date {
+        view.input(Input::Next, &[0; 3]);
+    }
+    view.input(Input::Select, &[2026, 10, 18]);
+    view.input(Input::Next, &[0; 3]);
+    view.input(Input::Up, &[0; 3]);
+    f("Date edit", &view);
+
+    let mut fault = View::default();
+    fault.input(Input::Next, &[0; 3]);
+    fault.set_fault(SensorError::OpenCircuit);
+    f("Sensor fault", &fault);
+
+    view.set_alarm(State::Ringing {
+        alarm: 0,
+        snoozes: 1,
+        elapsed: 0,
+    });
+    f("Alarm", &view);
+    view.set_alarm(State::Snoozed {
+        alarm: 0,
+        snoozes: 1,
+        remaining: 299,
+    });
+    f("Snoozed", &view);
+}
+
+/// File name of a sample, e.g. "date_edit.pbm" for "Date edit"
+pub fn file_name(name: &str, w: &mut impl Write) -> fmt::Result {
+    let words = name
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty());
+    for (i, word) in words.enumerate() {
+        if i > 0 {
+            w.write_char('_')?;
+        }
+        for c in word.chars() {
+            w.write_char(c.to_ascii_lowercase())?;
+        }
+    }
+    w.write_str(".pbm")
+}
+
+#[cfg(test)]
+mod test_draw {
+    use super::*;
+
+    fn render(view: &View) -> FrameBuffer {
+        let mut frame = FrameBuffer::default();
+        draw(&view.screen(), &mut frame).unwrap();
+        frame
+    }
+
+    #[test]
+    fn test_frame_buffer() {
+        let mut frame = FrameBuffer::default();
+        Pixel(Point::new(3, 1), BinaryColor::On)
+            .draw(&mut frame)
+            .unwrap();
+        // outside, ignored
+        Pixel(Point::new(-1, 40), BinaryColor::On)
+            .draw(&mut frame)
+            .unwrap();
+        assert!(frame.pixel(3, 1));
+        assert!(!frame.pixel(2, 1));
+        let mut pbm = String::new();
+        frame.write_pbm(&mut pbm).unwrap();
+        let lines: Vec<_> = pbm.lines().collect();
+        assert_eq!(lines.len(), 2 + HEIGHT as usize);
+        assert!(lines[3].starts_with("00010"));
+    }
+
+    #[test]
+    fn test_file_name() {
+        let mut name = String::new();
+        file_name("< Back", &mut name).unwrap();
+        assert_eq!(name, "back.pbm");
+    }
+
+    #[test]
+    fn test_underline() {
+        let mut edit = None;
+        samples(|name, view| {
+            if name == "Date edit" {
+                edit = Some(render(view));
+            }
+        });
+        // the month, characters 5 and 6, is underlined below the body
+        let frame = edit.unwrap();
+        assert!((50..70).all(|x| frame.pixel(x, 31)));
+        assert!(!frame.pixel(49, 31) && !frame.pixel(70, 31));
+    }
+
+    /// Compare every sample with its snapshot in `snapshots`, written by
+    /// `cargo run --example snapshots`
+    #[test]
+    fn test_snapshots() {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/");
+        let mut names = vec![];
+        samples(|name, view| {
+            let mut file = String::from(dir);
+            file_name(name, &mut file).unwrap();
+            let mut pbm = String::new();
+            render(view).write_pbm(&mut pbm).unwrap();
+            let golden = std::fs::read_to_string(&file).unwrap_or_default();
+            assert!(pbm == golden, "{} differs from {}", name, file);
+            names.push(file);
+        });
+        assert_eq!(names.len(), ITEMS.len() + 4);
+    }
+}
@@ -0,0 +1,162 @@
+//! Temperature history
+//!
+//! Averages the temperature readings, taken once per second, into points of
+//! `period` readings each and keeps the last `N` points. The clock keeps one
+//! point per minute for two hours, shown as a graph and dumped as CSV by the
+//! `temp history` command.
+
+use core::fmt::{self, Write};
+
+use heapless::Deque;
+
+/// Points kept by the clock, two hours
+pub const HISTORY_LEN: usize = 120;
+/// Readings per point, one minute
+pub const HISTORY_PERIOD: u16 = 60;
+
+#[derive(Clone, Debug)]
+pub struct History<const N: usize> {
+    /// Averages in °C, oldest first
+    points: Deque<f32, N>,
+    period: u16,
+    sum: f32,
+    count: u16,
+}
+
+impl<const N: usize> History<N> {
+    /// Average `period` readings into each point
+    pub fn new(period: u16) -> Self {
+        Self {
+            points: Deque::new(),
+            period: period.max(1),
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    /// Readings per point
+    pub fn period(&self) -> u16 {
+        self.period
+    }
+
+    /// Add a reading in °C, returns true if it completed a point, the
+    /// oldest point is dropped when full
+    ///
+    /// # Examples
+    /// ```
+    /// use clock_core::history::History;
+    /// let mut history: History<4> = History::new(2);
+    /// assert!(!history.push(20.0));
+    /// assert!(history.push(21.0));
+    /// assert_eq!(history.iter().collect::<Vec<_>>(), [20.5]);
+    /// ```
+    pub fn push(&mut self, celsius: f32) -> bool {
+        self.sum += celsius;
+        self.count += 1;
+        if self.count < self.period {
+            return false;
+        }
+        if self.points.is_full() {
+            self.points.pop_front();
+        }
+        let _ = self.points.push_back(self.sum / self.count as f32);
+        self.sum = 0.0;
+        self.count = 0;
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Points in °C, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = f32> + '_ {
+        self.points.iter().copied()
+    }
+
+    /// Lowest and highest point in °C
+    pub fn min_max(&self) -> Option<(f32, f32)> {
+        let first = *self.points.front()?;
+        Some(
+            self.iter()
+                .fold((first, first), |(min, max), p| (min.min(p), max.max(p))),
+        )
+    }
+
+    /// Write the points as CSV, oldest first, with their age in readings
+    /// (seconds) and the temperature in °C
+    ///
+    /// # Examples
+    /// ```
+    /// use clock_core::history::History;
+    /// let mut history: History<4> = History::new(60);
+    /// for temp in [20.0, 21.25] {
+    ///     (0..60).for_each(|_| { history.push(temp); });
+    /// }
+    /// let mut csv = String::new();
+    /// history.write_csv(&mut csv).unwrap();
+    /// assert_eq!(csv, "age_s,celsius\r\n120,20.00\r\n60,21.25\r\n");
+    /// ```
+    pub fn write_csv(&self, w: &mut impl Write) -> fmt::Result {
+        write!(w, "age_s,celsius\r\n")?;
+        let len = self.len() as u32;
+        for (i, point) in self.iter().enumerate() {
+            let age = (len - i as u32) * self.period as u32;
+            write!(w, "{},{:.2}\r\n", age, point)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_history {
+    use super::*;
+
+    #[test]
+    fn test_average() {
+        let mut history: History<4> = History::new(3);
+        assert!(history.is_empty());
+        assert_eq!(history.min_max(), None);
+        let points: Vec<_> = [1.0, 2.0, 6.0, -1.0, -2.0]
+            .iter()
+            .map(|&t| history.push(t))
+            .collect();
+        assert_eq!(points, [false, false, true, false, false]);
+        assert_eq!(history.iter().collect::<Vec<_>>(), [3.0]);
+        assert!(history.push(0.0));
+        assert_eq!(history.iter().collect::<Vec<_>>(), [3.0, -1.0]);
+        assert_eq!(history.min_max(), Some((-1.0, 3.0)));
+    }
+
+    #[test]
+    fn test_ring() {
+        let mut history: History<3> = History::new(1);
+        for t in 0..5 {
+            history.push(t as f32);
+        }
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.iter().collect::<Vec<_>>(), [2.0, 3.0, 4.0]);
+        assert_eq!(history.min_max(), Some((2.0, 4.0)));
+    }
+
+    #[test]
+    fn test_csv() {
+        let mut history: History<HISTORY_LEN> = History::new(HISTORY_PERIOD);
+        let mut csv = String::new();
+        history.write_csv(&mut csv).unwrap();
+        assert_eq!(csv, "age_s,celsius\r\n");
+        for i in 0..3 * HISTORY_PERIOD {
+            history.push(-0.5 * (i / HISTORY_PERIOD) as f32);
+        }
+        csv.clear();
+        history.write_csv(&mut csv).unwrap();
+        assert_eq!(
+            csv,
+            "age_s,celsius\r\n180,0.00\r\n120,-0.50\r\n60,-1.00\r\n"
+        );
+    }
+}
@@ -0,0 +1,18 @@
+//! clock-core
+//!
+//! Hardware independent logic of the alarm clock, tested on host and used on target
+
+// no_std library except for test
+#![cfg_attr(not(test), no_std)]
+
+pub mod alarm;
+pub mod button;
+pub mod calendar;
+pub mod draw;
+pub mod history;
+pub mod menu;
+pub mod sensor;
+pub mod settings;
+pub mod thermistor;
+pub mod units;
+pub mod view;
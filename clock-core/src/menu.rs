@@ -0,0 +1,503 @@
+//! Menu
+//!
+//! The menu is a tree given by the `ITEMS` table, where each item names its
+//! parent. Browsing moves between siblings with `Next` and `Select` enters a
+//! submenu, leaves one through its `Back` item, or starts editing the
+//! numeric fields of an item. While editing, `Up`/`Down` change the selected
+//! field, `Next` selects the next field and `Select` commits the values.
+
+use core::fmt::{self, Write};
+use core::ops::Range;
+
+/// Most fields of an item
+pub const MAX_FIELDS: usize = 3;
+
+/// Field values of an item
+pub type Values = [i32; MAX_FIELDS];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Page {
+    Clock,
+    Temperature,
+    History,
+    Alarms,
+    Settings,
+    Time,
+    Date,
+    Brightness,
+    Units,
+    About,
+    Back,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    /// Shows its fields (if any), they can not be edited
+    View,
+    Submenu,
+    Edit,
+    /// Leaves the submenu
+    Back,
+}
+
+/// A numeric field, shown as one of `options` if given
+#[derive(Debug, PartialEq)]
+pub struct Field {
+    pub min: i32,
+    pub max: i32,
+    pub step: i32,
+    /// Zero padded to this many digits
+    pub width: usize,
+    pub options: &'static [&'static str],
+    pub suffix: &'static str,
+}
+
+impl Field {
+    const fn number(min: i32, max: i32, width: usize) -> Self {
+        Self {
+            min,
+            max,
+            step: 1,
+            width,
+            options: &[],
+            suffix: "",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Item {
+    pub page: Page,
+    pub title: &'static str,
+    pub parent: Option<Page>,
+    pub kind: Kind,
+    pub fields: &'static [Field],
+    /// Written between fields
+    pub separator: &'static str,
+    /// Shown by items without fields
+    pub text: &'static str,
+}
+
+impl Item {
+    const fn new(page: Page, title: &'static str, parent: Option<Page>, kind: Kind) -> Self {
+        Self {
+            page,
+            title,
+            parent,
+            kind,
+            fields: &[],
+            separator: "",
+            text: "",
+        }
+    }
+
+    /// Write the field values, returns the character range of field
+    /// `selected`
+    ///
+    /// # Examples
+    /// ```
+    /// use clock_core::menu::*;
+    /// let mut text = String::new();
+    /// let time = item(Page::Time);
+    /// let selected = time.write_values(&[7, 5, 0], Some(1), &mut text).unwrap();
+    /// assert_eq!(text, "07:05:00");
+    /// assert_eq!(selected, Some(3..5));
+    /// ```
+    pub fn write_values(
+        &self,
+        values: &Values,
+        selected: Option<usize>,
+        w: &mut impl Write,
+    ) -> Result<Option<Range<usize>>, fmt::Error> {
+        let mut counter = Counter { w, len: 0 };
+        let mut range = None;
+        for (i, (field, &value)) in self.fields.iter().zip(values).enumerate() {
+            if i > 0 {
+                counter.write_str(self.separator)?;
+            }
+            let start = counter.len;
+            match field.options.get(value as usize) {
+                Some(option) => counter.write_str(option)?,
+                None => write!(counter, "{:0width$}", value, width = field.width)?,
+            }
+            if selected == Some(i) {
+                range = Some(start..counter.len);
+            }
+            counter.write_str(field.suffix)?;
+        }
+        Ok(range)
+    }
+}
+
+/// Counts the characters written
+struct Counter<'a, W> {
+    w: &'a mut W,
+    len: usize,
+}
+
+impl<W: Write> Write for Counter<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.len += s.chars().count();
+        self.w.write_str(s)
+    }
+}
+
+const TIME: [Field; 3] = [
+    Field::number(0, 23, 2),
+    Field::number(0, 59, 2),
+    Field::number(0, 59, 2),
+];
+const DATE: [Field; 3] = [
+    Field::number(1970, 2099, 4),
+    Field::number(1, 12, 2),
+    Field::number(1, 31, 2),
+];
+const BRIGHTNESS: [Field; 1] = [Field {
+    step: 10,
+    suffix: "%",
+    ..Field::number(0, 100, 1)
+}];
+const UNITS: [Field; 1] = [Field {
+    options: &["C", "F", "K"],
+    ..Field::number(0, 2, 1)
+}];
+const ALARMS: [Field; 1] = [Field {
+    suffix: " active",
+    ..Field::number(0, 99, 1)
+}];
+
+/// The menu tree, children follow their parent
+pub const ITEMS: [Item; 11] = [
+    Item::new(Page::Clock, "Clock", None, Kind::View),
+    Item::new(Page::Temperature, "Temperature", None, Kind::View),
+    Item::new(Page::History, "History", None, Kind::View),
+    Item {
+        fields: &ALARMS,
+        ..Item::new(Page::Alarms, "Alarms", None, Kind::View)
+    },
+    Item::new(Page::Settings, "Settings", None, Kind::Submenu),
+    Item {
+        fields: &TIME,
+        separator: ":",
+        ..Item::new(Page::Time, "Time", Some(Page::Settings), Kind::Edit)
+    },
+    Item {
+        fields: &DATE,
+        separator: "-",
+        ..Item::new(Page::Date, "Date", Some(Page::Settings), Kind::Edit)
+    },
+    Item {
+        fields: &BRIGHTNESS,
+        ..Item::new(
+            Page::Brightness,
+            "Brightness",
+            Some(Page::Settings),
+            Kind::Edit,
+        )
+    },
+    Item {
+        fields: &UNITS,
+        ..Item::new(Page::Units, "Units", Some(Page::Settings), Kind::Edit)
+    },
+    Item {
+        text: concat!("v", env!("CARGO_PKG_VERSION")),
+        ..Item::new(Page::About, "About", Some(Page::Settings), Kind::View)
+    },
+    Item::new(Page::Back, "< Back", Some(Page::Settings), Kind::Back),
+];
+
+/// The item of `page`
+pub fn item(page: Page) -> &'static Item {
+    &ITEMS[index(page)]
+}
+
+fn index(page: Page) -> usize {
+    // every page has an item
+    ITEMS.iter().position(|item| item.page == page).unwrap_or(0)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Input {
+    Next,
+    Up,
+    Down,
+    Select,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Response {
+    /// Not used by the menu, e.g., `Up` while browsing
+    Ignored,
+    /// Page or field changed, redraw
+    Changed,
+    /// Editing done, apply the values to `Page`
+    Commit(Page, Values),
+}
+
+/// Ongoing edit of the current item
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Edit {
+    pub field: usize,
+    pub values: Values,
+}
+
+#[derive(Debug, Default)]
+pub struct Menu {
+    index: usize,
+    edit: Option<Edit>,
+}
+
+impl Menu {
+    pub fn item(&self) -> &'static Item {
+        &ITEMS[self.index]
+    }
+
+    pub fn page(&self) -> Page {
+        self.item().page
+    }
+
+    pub fn edit(&self) -> Option<&Edit> {
+        self.edit.as_ref()
+    }
+
+    /// Handle a button, `current` holds the values of the current item,
+    /// used as a start when editing
+    ///
+    /// # Examples
+    /// ```
+    /// use clock_core::menu::*;
+    /// let mut menu = Menu::default();
+    /// assert_eq!(menu.page(), Page::Clock);
+    /// assert_eq!(menu.input(Input::Next, &[0; 3]), Response::Changed);
+    /// assert_eq!(menu.page(), Page::Temperature);
+    /// ```
+    pub fn input(&mut self, input: Input, current: &Values) -> Response {
+        let item = self.item();
+        if let Some(edit) = &mut self.edit {
+            let field = &item.fields[edit.field];
+            let value = &mut edit.values[edit.field];
+            match input {
+                Input::Up => *value = step(field, *value, field.step),
+                Input::Down => *value = step(field, *value, -field.step),
+                Input::Next => edit.field = (edit.field + 1) % item.fields.len(),
+                Input::Select => {
+                    let values = edit.values;
+                    self.edit = None;
+                    return Response::Commit(item.page, values);
+                }
+            }
+            return Response::Changed;
+        }
+
+        match (input, item.kind) {
+            (Input::Next, _) => self.index = self.next_sibling(),
+            (Input::Select, Kind::Submenu) => self.index = index(item.page) + 1,
+            (Input::Select, Kind::Edit) => {
+                let mut values = *current;
+                for (value, field) in values.iter_mut().zip(item.fields) {
+                    *value = (*value).clamp(field.min, field.max);
+                }
+                self.edit = Some(Edit { field: 0, values });
+            }
+            (Input::Select, Kind::Back) => self.index = index(item.parent.unwrap_or(item.page)),
+            _ => return Response::Ignored,
+        }
+        Response::Changed
+    }
+
+    fn next_sibling(&self) -> usize {
+        let parent = self.item().parent;
+        (1..ITEMS.len())
+            .map(|i| (self.index + i) % ITEMS.len())
+            .find(|&i| ITEMS[i].parent == parent)
+            .unwrap_or(self.index)
+    }
+}
+
+/// Add `delta` to `value`, wrapping around within the range of `field`
+fn step(field: &Field, value: i32, delta: i32) -> i32 {
+    match value + delta {
+        v if v > field.max => field.min,
+        v if v < field.min => field.max,
+        v => v,
+    }
+}
+
+#[cfg(test)]
+mod test_menu {
+    use super::*;
+
+    fn press(menu: &mut Menu, inputs: &[Input]) -> Response {
+        inputs
+            .iter()
+            .map(|&input| menu.input(input, &[7, 30, 0]))
+            .last()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_table() {
+        // children follow their parent, all pages have an item
+        for (i, it) in ITEMS.iter().enumerate() {
+            assert_eq!(index(it.page), i);
+            if let Some(parent) = it.parent {
+                assert!(index(parent) < i);
+                assert_eq!(item(parent).kind, Kind::Submenu);
+            }
+            assert!(it.fields.len() <= MAX_FIELDS);
+            if it.kind == Kind::Edit {
+                assert!(!it.fields.is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn test_top_level() {
+        let mut menu = Menu::default();
+        let mut pages = vec![];
+        for _ in 0..6 {
+            pages.push(menu.page());
+            assert_eq!(menu.input(Input::Next, &[0; 3]), Response::Changed);
+        }
+        assert_eq!(
+            pages,
+            [
+                Page::Clock,
+                Page::Temperature,
+                Page::History,
+                Page::Alarms,
+                Page::Settings,
+                Page::Clock
+            ]
+        );
+        // up and down are left to the caller while browsing
+        assert_eq!(menu.input(Input::Up, &[0; 3]), Response::Ignored);
+        assert_eq!(menu.input(Input::Down, &[0; 3]), Response::Ignored);
+        // views can not be selected
+        assert_eq!(menu.input(Input::Select, &[0; 3]), Response::Ignored);
+    }
+
+    #[test]
+    fn test_submenu() {
+        let mut menu = Menu::default();
+        press(
+            &mut menu,
+            &[Input::Next, Input::Next, Input::Next, Input::Next],
+        );
+        assert_eq!(menu.page(), Page::Settings);
+        press(&mut menu, &[Input::Select]);
+        assert_eq!(menu.page(), Page::Time);
+        let mut pages = vec![];
+        for _ in 0..7 {
+            pages.push(menu.page());
+            menu.input(Input::Next, &[0; 3]);
+        }
+        assert_eq!(
+            pages,
+            [
+                Page::Time,
+                Page::Date,
+                Page::Brightness,
+                Page::Units,
+                Page::About,
+                Page::Back,
+                Page::Time
+            ]
+        );
+        press(&mut menu, &[Input::Next, Input::Next, Input::Next]);
+        assert_eq!(menu.page(), Page::About);
+        assert_eq!(menu.input(Input::Select, &[0; 3]), Response::Ignored);
+        press(&mut menu, &[Input::Next, Input::Select]);
+        assert_eq!(menu.page(), Page::Settings);
+    }
+
+    #[test]
+    fn test_edit() {
+        let mut menu = Menu::default();
+        press(
+            &mut menu,
+            &[
+                Input::Next,
+                Input::Next,
+                Input::Next,
+                Input::Next,
+                Input::Select,
+            ],
+        );
+        assert_eq!(menu.page(), Page::Time);
+        press(&mut menu, &[Input::Select]);
+        assert_eq!(
+            menu.edit(),
+            Some(&Edit {
+                field: 0,
+                values: [7, 30, 0]
+            })
+        );
+        // hour 7 -> 6 -> 5, minute 30 -> 31, second 0 -> 59
+        press(
+            &mut menu,
+            &[
+                Input::Down,
+                Input::Down,
+                Input::Next,
+                Input::Up,
+                Input::Next,
+                Input::Down,
+            ],
+        );
+        assert_eq!(menu.edit().unwrap().field, 2);
+        assert_eq!(
+            menu.input(Input::Select, &[0; 3]),
+            Response::Commit(Page::Time, [5, 31, 59])
+        );
+        assert_eq!(menu.edit(), None);
+        assert_eq!(menu.page(), Page::Time);
+    }
+
+    #[test]
+    fn test_edit_wraps_and_clamps() {
+        let mut menu = Menu {
+            index: index(Page::Brightness),
+            edit: None,
+        };
+        // out of range values are clamped when editing starts
+        menu.input(Input::Select, &[1000, 0, 0]);
+        assert_eq!(menu.edit().unwrap().values[0], 100);
+        menu.input(Input::Up, &[0; 3]);
+        assert_eq!(menu.edit().unwrap().values[0], 0);
+        menu.input(Input::Down, &[0; 3]);
+        menu.input(Input::Down, &[0; 3]);
+        assert_eq!(menu.edit().unwrap().values[0], 90);
+        // the field index wraps too
+        menu.input(Input::Next, &[0; 3]);
+        assert_eq!(menu.edit().unwrap().field, 0);
+    }
+
+    #[test]
+    fn test_write_values() {
+        let mut text = String::new();
+        let range = item(Page::Date)
+            .write_values(&[2026, 3, 9], Some(2), &mut text)
+            .unwrap();
+        assert_eq!(text, "2026-03-09");
+        assert_eq!(range, Some(8..10));
+
+        text.clear();
+        let range = item(Page::Units)
+            .write_values(&[1, 0, 0], Some(0), &mut text)
+            .unwrap();
+        assert_eq!((text.as_str(), range), ("F", Some(0..1)));
+
+        text.clear();
+        let range = item(Page::Brightness)
+            .write_values(&[50, 0, 0], None, &mut text)
+            .unwrap();
+        assert_eq!((text.as_str(), range), ("50%", None));
+
+        text.clear();
+        item(Page::Alarms)
+            .write_values(&[2, 0, 0], None, &mut text)
+            .unwrap();
+        assert_eq!(text, "2 active");
+    }
+}
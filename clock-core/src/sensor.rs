@@ -0,0 +1,271 @@
+//! Sensor readings
+//!
+//! Fault detection and filtering of the thermistor readings. A reading is
+//! the median of a few ADC samples, which rejects single spikes, and the
+//! temperatures are smoothed by an exponential moving average (EMA). The
+//! resistance is measured relative to the supply, so it does not drift with
+//! the battery voltage, and a resistance outside of what the thermistor can
+//! have is reported as an open or short circuit instead of being turned
+//! into a temperature. The on-die sensor of the nRF gives a second, coarse
+//! reading to cross-check the thermistor with.
+
+use heapless::Vec;
+
+/// Temperature in °C
+pub type Celsius = f32;
+
+/// Lowest plausible thermistor resistance, a 10 kΩ NTC has about 340 Ω at
+/// 125 °C
+pub const MIN_OHMS: f32 = 100.0;
+/// Highest plausible thermistor resistance, about 330 kΩ at -40 °C
+pub const MAX_OHMS: f32 = 1_000_000.0;
+/// Most ADC samples per reading
+pub const MAX_SAMPLES: usize = 15;
+/// Largest expected difference between the thermistor and the on-die
+/// sensor, which runs a few degrees warm
+pub const MAX_DIE_DIFFERENCE: f32 = 10.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SensorError {
+    /// The thermistor is disconnected
+    OpenCircuit,
+    /// The thermistor or its wires are shorted
+    ShortCircuit,
+    /// The ADC failed to convert
+    AdcError,
+}
+
+impl SensorError {
+    /// Name for serial replies
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::OpenCircuit => "OpenCircuit",
+            Self::ShortCircuit => "ShortCircuit",
+            Self::AdcError => "AdcError",
+        }
+    }
+
+    /// Text for the display, at most 12 characters to fit the large font
+    pub fn text(self) -> &'static str {
+        match self {
+            Self::OpenCircuit => "Open circuit",
+            Self::ShortCircuit => "Shorted",
+            Self::AdcError => "ADC error",
+        }
+    }
+}
+
+/// Check that a thermistor resistance is plausible
+///
+/// # Examples
+/// ```
+/// use clock_core::sensor::*;
+/// assert_eq!(check_ohms(10_000.0), Ok(10_000.0));
+/// assert_eq!(check_ohms(f32::INFINITY), Err(SensorError::OpenCircuit));
+/// assert_eq!(check_ohms(0.0), Err(SensorError::ShortCircuit));
+/// ```
+pub fn check_ohms(ohms: f32) -> Result<f32, SensorError> {
+    match ohms {
+        ohms if ohms.is_nan() || ohms > MAX_OHMS => Err(SensorError::OpenCircuit),
+        ohms if ohms < MIN_OHMS => Err(SensorError::ShortCircuit),
+        ohms => Ok(ohms),
+    }
+}
+
+/// Resistance of the lower leg of a divider with `r_ref` to the supply,
+/// from ADC readings of the divider and of the supply with the same gain
+/// and reference. The ratio of the readings is independent of the supply
+/// voltage.
+///
+/// # Examples
+/// ```
+/// use clock_core::sensor::*;
+/// // at 3.0 and 2.5 V supply
+/// assert_eq!(divider_ohms(1706, 3412, 10_000.0), Ok(10_000.0));
+/// assert_eq!(divider_ohms(1422, 2844, 10_000.0), Ok(10_000.0));
+/// ```
+pub fn divider_ohms(reading: i16, supply: i16, r_ref: f32) -> Result<f32, SensorError> {
+    if supply <= 0 {
+        return Err(SensorError::AdcError);
+    }
+    // the input is pulled up to the supply when open, and the SAADC may
+    // read slightly below 0 when shorted
+    let (reading, supply) = (reading.max(0) as f32, supply as f32);
+    if reading >= supply {
+        return Err(SensorError::OpenCircuit);
+    }
+    check_ohms(r_ref * reading / (supply - reading))
+}
+
+/// Returns true if the thermistor and the on-die sensor agree within
+/// `MAX_DIE_DIFFERENCE`
+///
+/// # Examples
+/// ```
+/// use clock_core::sensor::*;
+/// assert!(cross_check(21.5, 25.25));
+/// assert!(!cross_check(-20.0, 25.25));
+/// ```
+pub fn cross_check(thermistor: Celsius, die: Celsius) -> bool {
+    (thermistor - die).abs() <= MAX_DIE_DIFFERENCE
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FilterConfig {
+    /// ADC samples per reading, the median is used, 1..=`MAX_SAMPLES`
+    pub samples: u8,
+    /// Weight of a new reading in the moving average, 0 < alpha <= 1,
+    /// 1 turns the average off
+    pub alpha: f32,
+}
+
+impl Default for FilterConfig {
+    fn default() -> Self {
+        Self {
+            samples: 5,
+            alpha: 0.25,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Filter {
+    config: FilterConfig,
+    average: Option<f32>,
+}
+
+impl Filter {
+    pub fn new(config: FilterConfig) -> Self {
+        Self {
+            config: FilterConfig {
+                samples: config.samples.clamp(1, MAX_SAMPLES as u8),
+                alpha: config.alpha.clamp(f32::EPSILON, 1.0),
+            },
+            average: None,
+        }
+    }
+
+    pub fn config(&self) -> FilterConfig {
+        self.config
+    }
+
+    /// The median of `config.samples` samples taken by `read`, the first
+    /// error is returned as is
+    ///
+    /// # Examples
+    /// ```
+    /// use clock_core::sensor::*;
+    /// let filter = Filter::new(FilterConfig { samples: 3, alpha: 1.0 });
+    /// let mut samples = [2000, 4095, 2010].into_iter();
+    /// assert_eq!(filter.median(|| samples.next().ok_or(())), Ok(2010));
+    /// ```
+    pub fn median<E>(&self, mut read: impl FnMut() -> Result<i16, E>) -> Result<i16, E> {
+        let mut samples: Vec<i16, MAX_SAMPLES> = Vec::new();
+        for _ in 0..self.config.samples {
+            let _ = samples.push(read()?);
+        }
+        samples.sort_unstable();
+        Ok(samples[samples.len() / 2])
+    }
+
+    /// Add a reading to the moving average and return the average, the
+    /// first reading after a `reset` is taken as is
+    pub fn smooth(&mut self, value: f32) -> f32 {
+        let average = match self.average {
+            Some(average) => average + self.config.alpha * (value - average),
+            None => value,
+        };
+        self.average = Some(average);
+        average
+    }
+
+    /// Forget the average, e.g., after a fault, so that the readings before
+    /// it do not linger
+    pub fn reset(&mut self) {
+        self.average = None;
+    }
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self::new(FilterConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod test_sensor {
+    use super::*;
+
+    #[test]
+    fn test_check_ohms() {
+        assert_eq!(check_ohms(332_094.0), Ok(332_094.0));
+        assert_eq!(check_ohms(388.6), Ok(388.6));
+        assert_eq!(check_ohms(f32::NAN), Err(SensorError::OpenCircuit));
+        assert_eq!(check_ohms(2e6), Err(SensorError::OpenCircuit));
+        assert_eq!(check_ohms(-5.0), Err(SensorError::ShortCircuit));
+    }
+
+    #[test]
+    fn test_divider_ohms() {
+        // 12 bit readings, 3.6 V full scale
+        let supply = |volts: f32| (volts / 3.6 * 4095.0) as i16;
+        for volts in [3.3, 3.0, 2.4] {
+            // half the supply, R = Rref at any voltage
+            let ohms = divider_ohms(supply(volts / 2.0), supply(volts), 10_000.0).unwrap();
+            assert!((ohms - 10_000.0).abs() < 20.0, "{} V: {}", volts, ohms);
+        }
+        assert_eq!(
+            divider_ohms(3412, 3412, 10_000.0),
+            Err(SensorError::OpenCircuit)
+        );
+        assert_eq!(
+            divider_ohms(-3, 3412, 10_000.0),
+            Err(SensorError::ShortCircuit)
+        );
+        assert_eq!(divider_ohms(100, 0, 10_000.0), Err(SensorError::AdcError));
+    }
+
+    #[test]
+    fn test_cross_check() {
+        assert!(cross_check(20.0, 30.0));
+        assert!(!cross_check(20.0, 30.5));
+        assert!(!cross_check(f32::NAN, 20.0));
+    }
+
+    #[test]
+    fn test_median() {
+        let filter = Filter::new(FilterConfig {
+            samples: 5,
+            alpha: 1.0,
+        });
+        // spikes in both directions are rejected
+        let mut samples = [1500, 0, 1490, 4095, 1510].into_iter();
+        assert_eq!(filter.median(|| samples.next().ok_or(())), Ok(1500));
+        let mut samples = [Ok(1500), Err(SensorError::AdcError)].into_iter();
+        assert_eq!(
+            filter.median(|| samples.next().unwrap()),
+            Err(SensorError::AdcError)
+        );
+        // out of range configurations are clamped
+        let filter = Filter::new(FilterConfig {
+            samples: 0,
+            alpha: 2.0,
+        });
+        assert_eq!(filter.config().samples, 1);
+        assert_eq!(filter.config().alpha, 1.0);
+        assert_eq!(filter.median(|| Ok::<_, ()>(7)), Ok(7));
+    }
+
+    #[test]
+    fn test_smooth() {
+        let mut filter = Filter::new(FilterConfig {
+            samples: 1,
+            alpha: 0.5,
+        });
+        assert_eq!(filter.smooth(20.0), 20.0);
+        assert_eq!(filter.smooth(22.0), 21.0);
+        assert_eq!(filter.smooth(22.0), 21.5);
+        filter.reset();
+        assert_eq!(filter.smooth(30.0), 30.0);
+    }
+}
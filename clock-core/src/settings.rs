@@ -0,0 +1,504 @@
+//! Persistent settings
+//!
+//! `Settings` is serialized into a fixed size record with a magic number,
+//! format version, sequence number and CRC-32. `Store` keeps the records in
+//! two flash pages: each save appends a record to the active page, and only
+//! when it is full is the other page erased and written, so every page is
+//! erased once per `SLOTS` saves. Loading picks the valid record with the
+//! highest sequence number, a torn or corrupted record is skipped.
+
+use crate::alarm::{Alarm, LABEL_LEN, MAX_ALARMS, SNOOZE_MINUTES};
+use crate::thermistor::Model;
+use crate::units::TemperatureUnit;
+use embedded_storage::nor_flash::NorFlash;
+use heapless::String;
+
+/// Format version, records of other versions are ignored
+pub const VERSION: u8 = 2;
+/// Size of a record in flash, a multiple of the flash write size
+pub const RECORD_LEN: usize = 208;
+/// Default LED brightness, half of the PWM range
+pub const BRIGHTNESS: u16 = 500;
+
+const MAGIC: [u8; 2] = *b"CK";
+const HEADER_LEN: usize = 8;
+const ALARM_LEN: usize = 5 + LABEL_LEN;
+const MODEL_OFFSET: usize = 8 + MAX_ALARMS * ALARM_LEN;
+const PAYLOAD_LEN: usize = MODEL_OFFSET + Model::LEN;
+const CRC_OFFSET: usize = HEADER_LEN + PAYLOAD_LEN;
+const _: () = assert!(CRC_OFFSET + 4 <= RECORD_LEN && RECORD_LEN.is_multiple_of(4));
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    pub alarms: [Option<Alarm>; MAX_ALARMS],
+    /// LED brightness, 0..=1000
+    pub brightness: u16,
+    /// Local time offset from UTC in minutes
+    pub tz_offset_minutes: i16,
+    pub unit: TemperatureUnit,
+    /// Added to the thermistor reading, in 1/100 °C
+    pub calibration: i16,
+    /// Thermistor resistance to temperature
+    pub model: Model,
+    pub snooze_minutes: u8,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            alarms: Default::default(),
+            brightness: BRIGHTNESS,
+            tz_offset_minutes: 0,
+            unit: TemperatureUnit::Celsius,
+            calibration: 0,
+            model: Model::default(),
+            snooze_minutes: SNOOZE_MINUTES,
+        }
+    }
+}
+
+impl Settings {
+    /// Serialize into a record with sequence number `sequence`
+    pub fn to_record(&self, sequence: u32) -> [u8; RECORD_LEN] {
+        let mut record = [0xff; RECORD_LEN];
+        record[..2].copy_from_slice(&MAGIC);
+        record[2] = VERSION;
+        record[3] = 0;
+        record[4..8].copy_from_slice(&sequence.to_le_bytes());
+
+        let payload = &mut record[HEADER_LEN..CRC_OFFSET];
+        payload[0..2].copy_from_slice(&self.brightness.to_le_bytes());
+        payload[2..4].copy_from_slice(&self.tz_offset_minutes.to_le_bytes());
+        payload[4] = self.unit as u8;
+        payload[5] = self.snooze_minutes;
+        payload[6..8].copy_from_slice(&self.calibration.to_le_bytes());
+        payload[MODEL_OFFSET..].copy_from_slice(&self.model.to_bytes());
+        for (alarm, bytes) in self.alarms.iter().zip(payload[8..].chunks_mut(ALARM_LEN)) {
+            bytes.fill(0);
+            if let Some(alarm) = alarm {
+                bytes[0] = 1 | (alarm.enabled as u8) << 1;
+                bytes[1] = alarm.hour;
+                bytes[2] = alarm.minute;
+                bytes[3] = alarm.weekdays;
+                bytes[4] = alarm.label.len() as u8;
+                bytes[5..5 + alarm.label.len()].copy_from_slice(alarm.label.as_bytes());
+            }
+        }
+
+        let crc = crc32(&record[..CRC_OFFSET]);
+        record[CRC_OFFSET..CRC_OFFSET + 4].copy_from_slice(&crc.to_le_bytes());
+        record
+    }
+
+    /// Deserialize a record, returns the settings and sequence number, or
+    /// `None` if the record is blank, corrupted, of another version or holds
+    /// values the commands do not accept (a zero snooze, an alarm at an
+    /// impossible time)
+    pub fn from_record(record: &[u8; RECORD_LEN]) -> Option<(Self, u32)> {
+        let crc = u32::from_le_bytes(record[CRC_OFFSET..CRC_OFFSET + 4].try_into().ok()?);
+        if record[..2] != MAGIC || record[2] != VERSION || crc32(&record[..CRC_OFFSET]) != crc {
+            return None;
+        }
+        let sequence = u32::from_le_bytes(record[4..8].try_into().ok()?);
+
+        let payload = &record[HEADER_LEN..CRC_OFFSET];
+        let unit = TemperatureUnit::from_index(payload[4])?;
+        if payload[5] == 0 {
+            return None;
+        }
+        let mut settings = Self {
+            alarms: Default::default(),
+            brightness: u16::from_le_bytes([payload[0], payload[1]]),
+            tz_offset_minutes: i16::from_le_bytes([payload[2], payload[3]]),
+            unit,
+            calibration: i16::from_le_bytes([payload[6], payload[7]]),
+            model: Model::from_bytes(payload[MODEL_OFFSET..].try_into().ok()?)?,
+            snooze_minutes: payload[5],
+        };
+        for (alarm, bytes) in settings
+            .alarms
+            .iter_mut()
+            .zip(payload[8..].chunks(ALARM_LEN))
+        {
+            if bytes[0] & 1 == 0 {
+                continue;
+            }
+            if bytes[1] > 23 || bytes[2] > 59 {
+                return None;
+            }
+            let label = bytes[5..].get(..bytes[4] as usize)?;
+            *alarm = Some(Alarm {
+                hour: bytes[1],
+                minute: bytes[2],
+                enabled: bytes[0] & 2 != 0,
+                weekdays: bytes[3],
+                label: String::from(core::str::from_utf8(label).ok()?),
+            });
+        }
+        Some((settings, sequence))
+    }
+}
+
+/// CRC-32 (IEEE 802.3), bitwise to keep the code small
+///
+/// # Examples
+/// ```
+/// use clock_core::settings::*;
+/// assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+/// ```
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+/// Wear levelled settings storage in two erase pages of `flash`, starting
+/// at offset 0
+pub struct Store<F> {
+    flash: F,
+    sequence: u32,
+    page: u32,
+    /// Next free slot in `page`
+    slot: u32,
+}
+
+impl<F: NorFlash> Store<F> {
+    /// Records per page
+    pub const SLOTS: u32 = F::ERASE_SIZE as u32 / RECORD_LEN as u32;
+
+    /// Call `load` before the first `save` to continue in the active page
+    pub fn new(flash: F) -> Self {
+        Self {
+            flash,
+            sequence: 0,
+            page: 0,
+            slot: Self::SLOTS,
+        }
+    }
+
+    /// Load the latest valid settings, `None` if there are none
+    pub fn load(&mut self) -> Option<Settings> {
+        let mut latest = None;
+        let mut free = [Self::SLOTS; 2];
+        for page in 0..2 {
+            for slot in 0..Self::SLOTS {
+                let mut record = [0; RECORD_LEN];
+                if self
+                    .flash
+                    .read(Self::offset(page, slot), &mut record)
+                    .is_err()
+                {
+                    continue;
+                }
+                if record.iter().all(|&b| b == 0xff) {
+                    // the rest of the page is free, unless a save was torn
+                    if free[page as usize] == Self::SLOTS {
+                        free[page as usize] = slot;
+                    }
+                    continue;
+                }
+                free[page as usize] = Self::SLOTS;
+                match Settings::from_record(&record) {
+                    Some((settings, sequence))
+                        if latest.as_ref().is_none_or(|(_, s, _)| sequence > *s) =>
+                    {
+                        latest = Some((settings, sequence, page));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let (settings, sequence, page) = match latest {
+            Some((settings, sequence, page)) => (Some(settings), sequence, page),
+            None => (None, 0, 0),
+        };
+        self.sequence = sequence;
+        self.page = page;
+        self.slot = free[page as usize];
+        settings
+    }
+
+    /// Append `settings`, erasing the other page when the active one is full
+    pub fn save(&mut self, settings: &Settings) -> Result<(), F::Error> {
+        if self.slot >= Self::SLOTS {
+            self.page ^= 1;
+            self.slot = 0;
+            let from = Self::offset(self.page, 0);
+            self.flash.erase(from, from + F::ERASE_SIZE as u32)?;
+        }
+        let sequence = self.sequence.wrapping_add(1);
+        let record = settings.to_record(sequence);
+        self.flash
+            .write(Self::offset(self.page, self.slot), &record)?;
+        self.sequence = sequence;
+        self.slot += 1;
+        Ok(())
+    }
+
+    /// Erase both pages, the next load returns `None`
+    pub fn erase(&mut self) -> Result<(), F::Error> {
+        self.flash.erase(0, 2 * F::ERASE_SIZE as u32)?;
+        self.sequence = 0;
+        self.page = 0;
+        self.slot = 0;
+        Ok(())
+    }
+
+    fn offset(page: u32, slot: u32) -> u32 {
+        page * F::ERASE_SIZE as u32 + slot * RECORD_LEN as u32
+    }
+}
+
+#[cfg(test)]
+mod test_settings {
+    use super::*;
+    use crate::thermistor::SteinhartHart;
+    use embedded_storage::nor_flash::{ErrorType, NorFlashErrorKind, ReadNorFlash};
+
+    const PAGE: usize = 4096;
+
+    /// Flash in RAM, writes can only clear bits like real NOR flash
+    struct RamFlash {
+        data: Vec<u8>,
+        erases: [u32; 2],
+    }
+
+    impl RamFlash {
+        fn new() -> Self {
+            Self {
+                data: vec![0xff; 2 * PAGE],
+                erases: [0; 2],
+            }
+        }
+    }
+
+    impl ErrorType for &mut RamFlash {
+        type Error = NorFlashErrorKind;
+    }
+
+    impl ReadNorFlash for &mut RamFlash {
+        const READ_SIZE: usize = 1;
+
+        fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+            let offset = offset as usize;
+            bytes.copy_from_slice(&self.data[offset..offset + bytes.len()]);
+            Ok(())
+        }
+
+        fn capacity(&self) -> usize {
+            self.data.len()
+        }
+    }
+
+    impl NorFlash for &mut RamFlash {
+        const WRITE_SIZE: usize = 4;
+        const ERASE_SIZE: usize = PAGE;
+
+        fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+            for page in from as usize / PAGE..to as usize / PAGE {
+                self.erases[page] += 1;
+            }
+            self.data[from as usize..to as usize].fill(0xff);
+            Ok(())
+        }
+
+        fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+            let offset = offset as usize;
+            for (d, b) in self.data[offset..offset + bytes.len()]
+                .iter_mut()
+                .zip(bytes)
+            {
+                *d &= b;
+            }
+            Ok(())
+        }
+    }
+
+    fn settings(brightness: u16) -> Settings {
+        let mut settings = Settings {
+            brightness,
+            tz_offset_minutes: -330,
+            unit: TemperatureUnit::Kelvin,
+            calibration: -125,
+            model: Model::SteinhartHart(SteinhartHart {
+                a: 1.1e-3,
+                b: 2.4e-4,
+                c: 7.5e-8,
+            }),
+            snooze_minutes: 9,
+            ..Default::default()
+        };
+        settings.alarms[1] = Some(Alarm {
+            hour: 6,
+            minute: 45,
+            enabled: false,
+            weekdays: 0x1f,
+            label: String::from("work"),
+        });
+        settings.alarms[7] = Some(Alarm {
+            hour: 23,
+            minute: 0,
+            enabled: true,
+            weekdays: 0x7f,
+            label: String::from("sixteen chars!!!"),
+        });
+        settings
+    }
+
+    #[test]
+    fn test_record_round_trip() {
+        let record = settings(42).to_record(7);
+        assert_eq!(Settings::from_record(&record), Some((settings(42), 7)));
+        let record = Settings::default().to_record(0);
+        assert_eq!(
+            Settings::from_record(&record),
+            Some((Settings::default(), 0))
+        );
+    }
+
+    #[test]
+    fn test_record_corrupted() {
+        let record = settings(42).to_record(1);
+        for i in 0..RECORD_LEN {
+            let mut corrupted = record;
+            corrupted[i] ^= 0x10;
+            // bytes after the CRC are padding
+            if i < CRC_OFFSET + 4 {
+                assert_eq!(Settings::from_record(&corrupted), None, "byte {}", i);
+            }
+        }
+        assert_eq!(Settings::from_record(&[0xff; RECORD_LEN]), None);
+    }
+
+    #[test]
+    fn test_record_version() {
+        let mut record = settings(42).to_record(1);
+        record[2] = VERSION + 1;
+        let crc = crc32(&record[..CRC_OFFSET]);
+        record[CRC_OFFSET..CRC_OFFSET + 4].copy_from_slice(&crc.to_le_bytes());
+        assert_eq!(Settings::from_record(&record), None);
+    }
+
+    #[test]
+    fn test_record_out_of_range() {
+        // valid CRC, values the commands would not accept
+        let resealed = |offset: usize, value: u8| {
+            let mut record = settings(42).to_record(1);
+            record[offset] = value;
+            let crc = crc32(&record[..CRC_OFFSET]);
+            record[CRC_OFFSET..CRC_OFFSET + 4].copy_from_slice(&crc.to_le_bytes());
+            Settings::from_record(&record)
+        };
+        let alarm = HEADER_LEN + 8 + ALARM_LEN;
+        assert_eq!(resealed(HEADER_LEN + 5, 0), None);
+        assert_eq!(resealed(alarm + 1, 24), None);
+        assert_eq!(resealed(alarm + 2, 60), None);
+        assert!(resealed(HEADER_LEN + 5, 1).is_some());
+        assert!(resealed(alarm + 1, 23).is_some());
+        assert!(resealed(alarm + 2, 59).is_some());
+        // an unused slot is not checked
+        assert!(resealed(HEADER_LEN + 8 + 1, 24).is_some());
+    }
+
+    #[test]
+    fn test_load_blank() {
+        let mut flash = RamFlash::new();
+        assert_eq!(Store::new(&mut flash).load(), None);
+    }
+
+    #[test]
+    fn test_save_load() {
+        let mut flash = RamFlash::new();
+        let mut store = Store::new(&mut flash);
+        store.load();
+        store.save(&settings(1)).unwrap();
+        store.save(&settings(2)).unwrap();
+        assert_eq!(Store::new(&mut flash).load(), Some(settings(2)));
+    }
+
+    #[test]
+    fn test_wear_levelling() {
+        let mut flash = RamFlash::new();
+        let slots = Store::<&mut RamFlash>::SLOTS;
+        assert_eq!(slots, 19);
+        for i in 0..10 * slots {
+            // reload every time, as after a reset
+            let mut store = Store::new(&mut flash);
+            let expected = i.checked_sub(1).map(|i| settings(i as u16));
+            assert_eq!(store.load(), expected);
+            store.save(&settings(i as u16)).unwrap();
+        }
+        // the first page is used without an erase
+        assert_eq!(flash.erases, [4, 5]);
+    }
+
+    #[test]
+    fn test_corrupted_fallback() {
+        let mut flash = RamFlash::new();
+        let mut store = Store::new(&mut flash);
+        store.load();
+        store.save(&settings(1)).unwrap();
+        store.save(&settings(2)).unwrap();
+        // corrupt the latest record, the previous one is used
+        flash.data[RECORD_LEN + 20] ^= 1;
+        let mut store = Store::new(&mut flash);
+        assert_eq!(store.load(), Some(settings(1)));
+        // the corrupted slot is not reused
+        store.save(&settings(3)).unwrap();
+        assert_eq!(Store::new(&mut flash).load(), Some(settings(3)));
+        assert_eq!(&flash.data[2 * RECORD_LEN..2 * RECORD_LEN + 2], &MAGIC);
+
+        // corrupt everything, defaults are up to the caller
+        flash.data[20] ^= 1;
+        flash.data[2 * RECORD_LEN + 20] ^= 1;
+        assert_eq!(Store::new(&mut flash).load(), None);
+    }
+
+    #[test]
+    fn test_torn_write() {
+        let mut flash = RamFlash::new();
+        let mut store = Store::new(&mut flash);
+        store.load();
+        store.save(&settings(1)).unwrap();
+        // half a record written when power was lost
+        let record = settings(2).to_record(2);
+        flash.data[RECORD_LEN..RECORD_LEN + 64].copy_from_slice(&record[..64]);
+        let mut store = Store::new(&mut flash);
+        assert_eq!(store.load(), Some(settings(1)));
+        store.save(&settings(3)).unwrap();
+        assert_eq!(Store::new(&mut flash).load(), Some(settings(3)));
+    }
+
+    #[test]
+    fn test_page_switch() {
+        let mut flash = RamFlash::new();
+        let mut store = Store::new(&mut flash);
+        store.load();
+        for i in 0..=Store::<&mut RamFlash>::SLOTS {
+            store.save(&settings(i as u16)).unwrap();
+        }
+        // the newest record is first in the second page, the first page
+        // still holds older records
+        assert_eq!(&flash.data[PAGE..PAGE + 2], &MAGIC);
+        assert_eq!(flash.erases, [0, 1]);
+        assert_eq!(Store::new(&mut flash).load(), Some(settings(19)));
+    }
+
+    #[test]
+    fn test_erase() {
+        let mut flash = RamFlash::new();
+        let mut store = Store::new(&mut flash);
+        store.load();
+        store.save(&settings(1)).unwrap();
+        store.erase().unwrap();
+        assert_eq!(store.load(), None);
+        store.save(&settings(2)).unwrap();
+        assert_eq!(Store::new(&mut flash).load(), Some(settings(2)));
+    }
+}
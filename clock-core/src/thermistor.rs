@@ -0,0 +1,448 @@
+//! Thermistor models
+//!
+//! A `ThermistorModel` turns the resistance of an NTC thermistor into a
+//! temperature, either from the Beta equation, the Steinhart–Hart equation
+//! or by interpolating a datasheet table. `Calibration` collects reference
+//! points (a known temperature and the measured resistance) and fits the
+//! Beta or Steinhart–Hart coefficients to them.
+
+use core::fmt;
+
+use heapless::Vec;
+use libm::{log, logf};
+
+/// 0 °C in K
+const ZERO_CELSIUS: f32 = 273.15;
+
+pub trait ThermistorModel {
+    /// Temperature in °C at a resistance of `ohms`
+    fn celsius(&self, ohms: f32) -> f32;
+}
+
+/// A temperature in °C and the resistance in Ω at that temperature
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Reference {
+    pub celsius: f32,
+    pub ohms: f32,
+}
+
+impl Reference {
+    pub const fn new(celsius: f32, ohms: f32) -> Self {
+        Self { celsius, ohms }
+    }
+
+    /// ln(R) and 1/T in double precision for fitting
+    fn fit_terms(&self) -> (f64, f64) {
+        (
+            log(self.ohms as f64),
+            1.0 / (self.celsius as f64 + ZERO_CELSIUS as f64),
+        )
+    }
+}
+
+/// 1/T = 1/T0 + ln(R/R0)/B
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Beta {
+    /// Resistance in Ω at `t0`
+    pub r0: f32,
+    /// Reference temperature in °C
+    pub t0: f32,
+    /// B in K
+    pub beta: f32,
+}
+
+impl Beta {
+    /// 10 kΩ at 25 °C with B = 3950 K, the thermistor on the board
+    pub const NTC_10K_3950: Self = Self {
+        r0: 10_000.0,
+        t0: 25.0,
+        beta: 3950.0,
+    };
+
+    /// The curve through two reference points, `None` if they do not
+    /// describe an NTC thermistor
+    ///
+    /// # Examples
+    /// ```
+    /// use clock_core::thermistor::*;
+    /// let beta = Beta::fit(Reference::new(25.0, 10_000.0), Reference::new(80.0, 1256.0)).unwrap();
+    /// assert!((beta.beta - 3972.0).abs() < 1.0);
+    /// ```
+    pub fn fit(p1: Reference, p2: Reference) -> Option<Self> {
+        let ((l1, y1), (l2, y2)) = (p1.fit_terms(), p2.fit_terms());
+        let beta = ((l1 - l2) / (y1 - y2)) as f32;
+        (beta.is_finite() && beta > 0.0 && p1.ohms > 0.0).then_some(Self {
+            r0: p1.ohms,
+            t0: p1.celsius,
+            beta,
+        })
+    }
+}
+
+impl ThermistorModel for Beta {
+    fn celsius(&self, ohms: f32) -> f32 {
+        1.0 / (1.0 / (self.t0 + ZERO_CELSIUS) + logf(ohms / self.r0) / self.beta) - ZERO_CELSIUS
+    }
+}
+
+/// 1/T = A + B ln(R) + C ln(R)³
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SteinhartHart {
+    pub a: f32,
+    pub b: f32,
+    pub c: f32,
+}
+
+impl SteinhartHart {
+    /// The coefficients through three reference points, `None` if they do
+    /// not describe an NTC thermistor: two at the same temperature, or the
+    /// resistance not falling as the temperature rises
+    pub fn fit(points: &[Reference; 3]) -> Option<Self> {
+        // every pair, the resistance falls between distinct temperatures
+        let ntc = (0..3).all(|i| {
+            let (p, q) = (points[i], points[(i + 1) % 3]);
+            p.ohms > 0.0 && (p.celsius - q.celsius) * (p.ohms - q.ohms) < 0.0
+        });
+        if !ntc {
+            return None;
+        }
+        let [(l1, y1), (l2, y2), (l3, y3)] = points.map(|p| p.fit_terms());
+        let g2 = (y2 - y1) / (l2 - l1);
+        let g3 = (y3 - y1) / (l3 - l1);
+        let c = (g3 - g2) / (l3 - l2) / (l1 + l2 + l3);
+        let b = g2 - c * (l1 * l1 + l1 * l2 + l2 * l2);
+        let a = y1 - (b + l1 * l1 * c) * l1;
+        let fit = Self {
+            a: a as f32,
+            b: b as f32,
+            c: c as f32,
+        };
+        let finite = [fit.a, fit.b, fit.c].iter().all(|k| k.is_finite());
+        (finite && fit.b > 0.0).then_some(fit)
+    }
+}
+
+impl ThermistorModel for SteinhartHart {
+    fn celsius(&self, ohms: f32) -> f32 {
+        let l = logf(ohms);
+        1.0 / (self.a + self.b * l + self.c * l * l * l) - ZERO_CELSIUS
+    }
+}
+
+/// Piecewise interpolation of a datasheet R/T table, in ln(R) which is
+/// close to linear in temperature
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Table<'a> {
+    /// Ordered by temperature
+    points: &'a [Reference],
+}
+
+impl<'a> Table<'a> {
+    /// `points` must hold at least two points ordered by temperature
+    pub const fn new(points: &'a [Reference]) -> Self {
+        Self { points }
+    }
+}
+
+impl ThermistorModel for Table<'_> {
+    /// Outside the table the first or last segment is extended
+    fn celsius(&self, ohms: f32) -> f32 {
+        let l = logf(ohms);
+        let i = self
+            .points
+            .windows(2)
+            .position(|pair| ohms >= pair[1].ohms)
+            .unwrap_or(self.points.len().saturating_sub(2));
+        let (p1, p2) = (self.points[i], self.points[i + 1]);
+        let (l1, l2) = (logf(p1.ohms), logf(p2.ohms));
+        p1.celsius + (p2.celsius - p1.celsius) * (l - l1) / (l2 - l1)
+    }
+}
+
+/// The persistent model, fitted by `Calibration`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Model {
+    Beta(Beta),
+    SteinhartHart(SteinhartHart),
+}
+
+impl Default for Model {
+    fn default() -> Self {
+        Self::Beta(Beta::NTC_10K_3950)
+    }
+}
+
+impl ThermistorModel for Model {
+    fn celsius(&self, ohms: f32) -> f32 {
+        match self {
+            Self::Beta(beta) => beta.celsius(ohms),
+            Self::SteinhartHart(sh) => sh.celsius(ohms),
+        }
+    }
+}
+
+impl Model {
+    /// Serialized length
+    pub const LEN: usize = 13;
+
+    pub fn to_bytes(&self) -> [u8; Self::LEN] {
+        let (tag, k) = match *self {
+            Self::Beta(Beta { r0, t0, beta }) => (0, [r0, t0, beta]),
+            Self::SteinhartHart(SteinhartHart { a, b, c }) => (1, [a, b, c]),
+        };
+        let mut bytes = [tag; Self::LEN];
+        for (k, chunk) in k.iter().zip(bytes[1..].chunks_mut(4)) {
+            chunk.copy_from_slice(&k.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8; Self::LEN]) -> Option<Self> {
+        let mut k = [0.0; 3];
+        for (k, chunk) in k.iter_mut().zip(bytes[1..].chunks(4)) {
+            *k = f32::from_le_bytes(chunk.try_into().ok()?);
+        }
+        let [k0, k1, k2] = k;
+        match bytes[0] {
+            0 => Some(Self::Beta(Beta {
+                r0: k0,
+                t0: k1,
+                beta: k2,
+            })),
+            1 => Some(Self::SteinhartHart(SteinhartHart {
+                a: k0,
+                b: k1,
+                c: k2,
+            })),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Model {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Beta(Beta { r0, t0, beta }) => {
+                write!(f, "beta R0={:.1} T0={:.2} B={:.1}", r0, t0, beta)
+            }
+            Self::SteinhartHart(SteinhartHart { a, b, c }) => {
+                write!(f, "steinhart-hart A={:e} B={:e} C={:e}", a, b, c)
+            }
+        }
+    }
+}
+
+/// Most reference points of a calibration
+pub const MAX_REFERENCES: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FitError {
+    /// No reference points
+    NoReferences,
+    /// The points do not describe an NTC thermistor, e.g., two at the same
+    /// temperature
+    Degenerate,
+}
+
+/// Reference points entered one by one, e.g., with the sensor in ice water
+/// and at room temperature next to a reference thermometer
+#[derive(Debug, Default)]
+pub struct Calibration {
+    references: Vec<Reference, MAX_REFERENCES>,
+}
+
+impl Calibration {
+    /// Add a point, returns false if there are `MAX_REFERENCES` already
+    pub fn add(&mut self, reference: Reference) -> bool {
+        self.references.push(reference).is_ok()
+    }
+
+    pub fn references(&self) -> &[Reference] {
+        &self.references
+    }
+
+    pub fn clear(&mut self) {
+        self.references.clear();
+    }
+
+    /// Fit a model to the points. One point moves the Beta curve of `model`
+    /// (of the board thermistor for Steinhart–Hart) through it, two points
+    /// fit the Beta equation and three the Steinhart–Hart equation.
+    ///
+    /// # Examples
+    /// ```
+    /// use clock_core::thermistor::*;
+    /// let mut calibration = Calibration::default();
+    /// calibration.add(Reference::new(0.0, 32_554.0));
+    /// calibration.add(Reference::new(25.0, 10_000.0));
+    /// calibration.add(Reference::new(80.0, 1256.0));
+    /// let model = calibration.fit(&Model::default()).unwrap();
+    /// assert!((model.celsius(3605.0) - 50.0).abs() < 0.05);
+    /// ```
+    pub fn fit(&self, model: &Model) -> Result<Model, FitError> {
+        let fit = match *self.references {
+            [] => return Err(FitError::NoReferences),
+            [p] => {
+                let beta = match model {
+                    Model::Beta(beta) => beta.beta,
+                    Model::SteinhartHart(_) => Beta::NTC_10K_3950.beta,
+                };
+                (p.ohms > 0.0).then_some(Model::Beta(Beta {
+                    r0: p.ohms,
+                    t0: p.celsius,
+                    beta,
+                }))
+            }
+            [p1, p2] => Beta::fit(p1, p2).map(Model::Beta),
+            [p1, p2, p3] => SteinhartHart::fit(&[p1, p2, p3]).map(Model::SteinhartHart),
+            _ => None,
+        };
+        fit.ok_or(FitError::Degenerate)
+    }
+}
+
+#[cfg(test)]
+mod test_thermistor {
+    use super::*;
+
+    /// R/T table of a 10 kΩ NTC with B25/85 = 3977 K, Vishay NTCLE100E3103
+    const NTCLE100E3103: [Reference; 17] = [
+        Reference::new(-40.0, 332_094.0),
+        Reference::new(-30.0, 175_200.0),
+        Reference::new(-20.0, 96_358.0),
+        Reference::new(-10.0, 55_046.0),
+        Reference::new(0.0, 32_554.0),
+        Reference::new(10.0, 19_872.0),
+        Reference::new(20.0, 12_488.0),
+        Reference::new(25.0, 10_000.0),
+        Reference::new(30.0, 8059.0),
+        Reference::new(40.0, 5330.0),
+        Reference::new(50.0, 3605.0),
+        Reference::new(60.0, 2490.0),
+        Reference::new(70.0, 1753.0),
+        Reference::new(80.0, 1256.0),
+        Reference::new(90.0, 915.4),
+        Reference::new(100.0, 677.3),
+        Reference::new(110.0, 509.2),
+    ];
+
+    /// Largest error in °C against the table between `from` and `to` °C
+    fn max_error(model: &impl ThermistorModel, from: f32, to: f32) -> f32 {
+        NTCLE100E3103
+            .iter()
+            .filter(|p| (from..=to).contains(&p.celsius))
+            .map(|p| (model.celsius(p.ohms) - p.celsius).abs())
+            .fold(0.0, f32::max)
+    }
+
+    #[test]
+    fn test_beta() {
+        let beta = Beta {
+            beta: 3977.0,
+            ..Beta::NTC_10K_3950
+        };
+        assert!((beta.celsius(10_000.0) - 25.0).abs() < 1e-4);
+        // the Beta equation is only good near its reference range
+        assert!(max_error(&beta, 0.0, 100.0) < 0.8);
+        assert!(max_error(&beta, -40.0, 110.0) > 2.0);
+    }
+
+    #[test]
+    fn test_beta_fit() {
+        let [p1, p2] = [NTCLE100E3103[7], NTCLE100E3103[12]];
+        let beta = Beta::fit(p1, p2).unwrap();
+        assert!((beta.celsius(p1.ohms) - p1.celsius).abs() < 1e-3);
+        assert!((beta.celsius(p2.ohms) - p2.celsius).abs() < 1e-3);
+        assert!(max_error(&beta, 20.0, 80.0) < 0.3);
+        // same temperature twice, or resistance rising with temperature
+        assert_eq!(Beta::fit(p1, Reference::new(25.0, 9000.0)), None);
+        assert_eq!(Beta::fit(p1, Reference::new(70.0, 20_000.0)), None);
+    }
+
+    #[test]
+    fn test_steinhart_hart_fit() {
+        let sh =
+            SteinhartHart::fit(&[NTCLE100E3103[2], NTCLE100E3103[7], NTCLE100E3103[15]]).unwrap();
+        assert!(max_error(&sh, -40.0, 110.0) < 0.05);
+        let p = NTCLE100E3103[0];
+        assert_eq!(SteinhartHart::fit(&[p, p, NTCLE100E3103[1]]), None);
+        // one temperature at three resistances
+        let same = [10_000.0, 9000.0, 8000.0].map(|ohms| Reference::new(25.0, ohms));
+        assert_eq!(SteinhartHart::fit(&same), None);
+        // resistance rising with temperature
+        let [p1, p2, p3] = [NTCLE100E3103[2], NTCLE100E3103[7], NTCLE100E3103[15]];
+        assert_eq!(
+            SteinhartHart::fit(&[p1, p2, Reference::new(p3.celsius, 200_000.0)]),
+            None
+        );
+        assert_eq!(
+            SteinhartHart::fit(&[p1, p2, Reference::new(60.0, 0.0)]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_table() {
+        let table = Table::new(&NTCLE100E3103);
+        assert_eq!(max_error(&table, -40.0, 110.0), 0.0);
+        // between points, and extended beyond the ends
+        assert!((table.celsius(4383.0) - 45.0).abs() < 0.05);
+        assert!((table.celsius(388.6) - 120.0).abs() < 1.0);
+        assert!((table.celsius(400_000.0) + 43.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_calibration() {
+        let mut calibration = Calibration::default();
+        assert_eq!(
+            calibration.fit(&Model::default()),
+            Err(FitError::NoReferences)
+        );
+
+        // one point keeps B and moves the curve through the point
+        calibration.add(Reference::new(21.0, 10_000.0));
+        let Ok(Model::Beta(beta)) = calibration.fit(&Model::default()) else {
+            panic!("not a Beta model");
+        };
+        assert_eq!(beta.beta, 3950.0);
+        assert!((beta.celsius(10_000.0) - 21.0).abs() < 1e-4);
+
+        calibration.add(NTCLE100E3103[12]);
+        assert!(matches!(
+            calibration.fit(&Model::default()),
+            Ok(Model::Beta(_))
+        ));
+        calibration.add(NTCLE100E3103[4]);
+        assert!(!calibration.add(NTCLE100E3103[5]));
+        let model = calibration.fit(&Model::default()).unwrap();
+        assert!(matches!(model, Model::SteinhartHart(_)));
+        assert!((model.celsius(10_000.0) - 21.0).abs() < 1e-3);
+
+        calibration.clear();
+        calibration.add(NTCLE100E3103[4]);
+        calibration.add(NTCLE100E3103[4]);
+        assert_eq!(
+            calibration.fit(&Model::default()),
+            Err(FitError::Degenerate)
+        );
+    }
+
+    #[test]
+    fn test_model_bytes() {
+        for model in [
+            Model::default(),
+            Model::SteinhartHart(SteinhartHart {
+                a: 1.1e-3,
+                b: 2.3e-4,
+                c: 9.5e-8,
+            }),
+        ] {
+            assert_eq!(Model::from_bytes(&model.to_bytes()), Some(model));
+        }
+        assert_eq!(Model::from_bytes(&[2; Model::LEN]), None);
+        assert_eq!(
+            Model::default().to_string(),
+            "beta R0=10000.0 T0=25.00 B=3950.0"
+        );
+    }
+}
@@ -0,0 +1,135 @@
+//! Temperature units
+//!
+//! The unit setting and `Temperature`, which formats a temperature in °C in
+//! any unit. The display, the serial replies and the logs all format
+//! through it, so they agree on the unit, rounding and symbol.
+
+use core::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TemperatureUnit {
+    Celsius,
+    Fahrenheit,
+    Kelvin,
+}
+
+impl TemperatureUnit {
+    /// The unit at `index` in the order of the Units menu and the settings
+    /// record, `None` if out of range
+    pub fn from_index(index: u8) -> Option<Self> {
+        match index {
+            0 => Some(Self::Celsius),
+            1 => Some(Self::Fahrenheit),
+            2 => Some(Self::Kelvin),
+            _ => None,
+        }
+    }
+
+    /// Convert a temperature in °C to this unit
+    pub fn from_celsius(self, celsius: f32) -> f32 {
+        match self {
+            Self::Celsius => celsius,
+            Self::Fahrenheit => celsius * 9.0 / 5.0 + 32.0,
+            Self::Kelvin => celsius + 273.15,
+        }
+    }
+
+    /// Convert a temperature in this unit to °C
+    pub fn to_celsius(self, value: f32) -> f32 {
+        match self {
+            Self::Celsius => value,
+            Self::Fahrenheit => (value - 32.0) * 5.0 / 9.0,
+            Self::Kelvin => value - 273.15,
+        }
+    }
+
+    /// ASCII symbol, the display fonts have no degree sign
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Self::Celsius => "C",
+            Self::Fahrenheit => "F",
+            Self::Kelvin => "K",
+        }
+    }
+}
+
+/// A temperature in °C formatted in `unit`, with one decimal unless a
+/// precision is given, and a space before the symbol unless the alternate
+/// form `{:#}` is used for narrow labels
+///
+/// # Examples
+/// ```
+/// use clock_core::units::*;
+/// let temp = Temperature::new(21.5, TemperatureUnit::Fahrenheit);
+/// assert_eq!(format!("{}", temp), "70.7 F");
+/// assert_eq!(format!("{:.2}", temp), "70.70 F");
+/// assert_eq!(format!("{:#}", temp), "70.7F");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Temperature {
+    pub celsius: f32,
+    pub unit: TemperatureUnit,
+}
+
+impl Temperature {
+    pub fn new(celsius: f32, unit: TemperatureUnit) -> Self {
+        Self { celsius, unit }
+    }
+}
+
+impl fmt::Display for Temperature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = self.unit.from_celsius(self.celsius);
+        let precision = f.precision().unwrap_or(1);
+        let space = if f.alternate() { "" } else { " " };
+        write!(f, "{:.*}{}{}", precision, value, space, self.unit.symbol())
+    }
+}
+
+#[cfg(test)]
+mod test_units {
+    use super::*;
+
+    const UNITS: [TemperatureUnit; 3] = [
+        TemperatureUnit::Celsius,
+        TemperatureUnit::Fahrenheit,
+        TemperatureUnit::Kelvin,
+    ];
+
+    #[test]
+    fn test_from_celsius() {
+        assert_eq!(TemperatureUnit::Celsius.from_celsius(21.5), 21.5);
+        assert_eq!(TemperatureUnit::Fahrenheit.from_celsius(-40.0), -40.0);
+        assert_eq!(TemperatureUnit::Fahrenheit.from_celsius(100.0), 212.0);
+        assert_eq!(TemperatureUnit::Kelvin.from_celsius(0.0), 273.15);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for unit in UNITS {
+            for celsius in [-40.0, 0.0, 21.5, 125.0] {
+                let back = unit.to_celsius(unit.from_celsius(celsius));
+                assert!((back - celsius).abs() < 1e-4, "{:?} {}", unit, celsius);
+            }
+        }
+    }
+
+    #[test]
+    fn test_index() {
+        for (i, unit) in UNITS.iter().enumerate() {
+            assert_eq!(TemperatureUnit::from_index(i as u8), Some(*unit));
+            assert_eq!(*unit as u8, i as u8);
+        }
+        assert_eq!(TemperatureUnit::from_index(3), None);
+    }
+
+    #[test]
+    fn test_format() {
+        let format = |celsius, unit| format!("{}", Temperature::new(celsius, unit));
+        assert_eq!(format(-0.04, TemperatureUnit::Celsius), "-0.0 C");
+        assert_eq!(format(25.0, TemperatureUnit::Kelvin), "298.1 K");
+        assert_eq!(format(-40.0, TemperatureUnit::Fahrenheit), "-40.0 F");
+        let temp = Temperature::new(20.0, TemperatureUnit::Kelvin);
+        assert_eq!(format!("{:#.0}", temp), "293K");
+    }
+}
@@ -0,0 +1,390 @@
+//! Display view-model
+//!
+//! Caches what the screen shows (the time, the last temperature or sensor
+//! fault, the alarm state and the menu) and turns it into a `Screen` of text, so that the
+//! display only has to draw it. Switching pages shows the cached values
+//! right away instead of waiting for the next update.
+
+use core::fmt::Write;
+use core::ops::Range;
+
+use heapless::{String, Vec};
+
+use crate::alarm::State;
+use crate::history::{History, HISTORY_LEN, HISTORY_PERIOD};
+use crate::menu::{self, Input, Menu, Page, Response, Values};
+use crate::sensor::SensorError;
+use crate::units::{Temperature, TemperatureUnit};
+
+/// Longest title, "Parent / Title"
+pub const TITLE_LEN: usize = 24;
+/// Longest body, as wide as the display in the large font
+pub const BODY_LEN: usize = 16;
+/// Longest graph label, e.g. "-40.0F"
+pub const LABEL_LEN: usize = 8;
+
+/// Text of a screen
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Screen {
+    /// Drawn small above the body, no title if empty
+    pub title: String<TITLE_LEN>,
+    pub body: String<BODY_LEN>,
+    /// Characters of `body` to underline, the edited field
+    pub underline: Option<Range<usize>>,
+    /// Drawn instead of the title and body
+    pub graph: Option<Graph>,
+}
+
+/// A sparkline of the temperature history
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Graph {
+    /// Oldest first, the last one is the newest
+    pub points: Vec<f32, HISTORY_LEN>,
+    pub min: f32,
+    pub max: f32,
+    /// `min` and `max` in the display unit
+    pub min_label: String<LABEL_LEN>,
+    pub max_label: String<LABEL_LEN>,
+}
+
+impl Screen {
+    fn new(body: &str) -> Self {
+        let mut screen = Self::default();
+        let _ = screen.body.push_str(body);
+        screen
+    }
+}
+
+#[derive(Debug)]
+pub struct View {
+    menu: Menu,
+    /// Values of the current page
+    values: Values,
+    time: (u8, u8, u8),
+    temperature: Option<f32>,
+    /// Shown instead of the temperature until the next reading
+    fault: Option<SensorError>,
+    history: History<HISTORY_LEN>,
+    unit: TemperatureUnit,
+    alarm: State,
+}
+
+impl Default for View {
+    fn default() -> Self {
+        Self {
+            menu: Menu::default(),
+            values: [0; menu::MAX_FIELDS],
+            time: (0, 0, 0),
+            temperature: None,
+            fault: None,
+            history: History::new(HISTORY_PERIOD),
+            unit: TemperatureUnit::Celsius,
+            alarm: State::Idle,
+        }
+    }
+}
+
+impl View {
+    pub fn menu(&self) -> &Menu {
+        &self.menu
+    }
+
+    /// Pass a button to the menu, see `Menu::input`
+    pub fn input(&mut self, input: Input, current: &Values) -> Response {
+        self.menu.input(input, current)
+    }
+
+    /// Set the field values of the current page, ignored while editing
+    pub fn set_values(&mut self, values: &Values) {
+        self.values = *values;
+    }
+
+    pub fn unit(&self) -> TemperatureUnit {
+        self.unit
+    }
+
+    pub fn set_unit(&mut self, unit: TemperatureUnit) {
+        self.unit = unit;
+    }
+
+    /// Returns true if the time is on screen
+    pub fn set_time(&mut self, hour: u8, minute: u8, second: u8) -> bool {
+        self.time = (hour, minute, second);
+        self.shows(Page::Clock)
+    }
+
+    /// Set the temperature in °C and add it to the history, returns true
+    /// if the screen changed
+    pub fn set_temperature(&mut self, celsius: f32) -> bool {
+        self.temperature = Some(celsius);
+        self.fault = None;
+        let point = self.history.push(celsius);
+        self.shows(Page::Temperature) || point && self.shows(Page::History)
+    }
+
+    /// The sensor failed, the temperature is not shown and the history
+    /// skips the reading, returns true if the screen changed
+    pub fn set_fault(&mut self, fault: SensorError) -> bool {
+        let changed = self.fault != Some(fault);
+        self.temperature = None;
+        self.fault = Some(fault);
+        changed && self.shows(Page::Temperature)
+    }
+
+    pub fn history(&self) -> &History<HISTORY_LEN> {
+        &self.history
+    }
+
+    /// Returns true if the screen changed
+    pub fn set_alarm(&mut self, state: State) -> bool {
+        let changed = state != self.alarm;
+        self.alarm = state;
+        changed
+    }
+
+    /// The alarm covers the menu while it is ringing or snoozed
+    fn shows(&self, page: Page) -> bool {
+        self.alarm == State::Idle && self.menu.page() == page
+    }
+
+    /// What to draw
+    ///
+    /// # Examples
+    /// ```
+    /// use clock_core::view::*;
+    /// let mut view = View::default();
+    /// assert!(view.set_time(7, 5, 0));
+    /// assert_eq!(view.screen().body, "07:05:00");
+    /// ```
+    pub fn screen(&self) -> Screen {
+        let mut screen = Screen::default();
+        let body = &mut screen.body;
+        let _ = match self.alarm {
+            State::Ringing { snoozes: 0, .. } => write!(body, "ALARM!"),
+            State::Ringing { snoozes, .. } => write!(body, "ALARM! Z{}", snoozes),
+            State::Snoozed {
+                snoozes, remaining, ..
+            } => write!(
+                body,
+                "Z{} {:02}:{:02}",
+                snoozes,
+                remaining / 60,
+                remaining % 60
+            ),
+            State::Idle => return self.page(),
+        };
+        screen
+    }
+
+    fn page(&self) -> Screen {
+        let item = self.menu.item();
+        let mut screen = Screen::default();
+        let body = &mut screen.body;
+        let _ = match item.page {
+            Page::Clock => {
+                let (h, m, s) = self.time;
+                write!(body, "{:02}:{:02}:{:02}", h, m, s)
+            }
+            Page::Temperature => match (self.temperature, self.fault) {
+                (_, Some(fault)) => {
+                    let mut screen = Screen::new(fault.text());
+                    let _ = screen.title.push_str("Sensor fault");
+                    return screen;
+                }
+                (Some(celsius), None) => {
+                    write!(body, "Temp: {}", Temperature::new(celsius, self.unit))
+                }
+                (None, None) => write!(body, "Temp: --.- {}", self.unit.symbol()),
+            },
+            Page::History => return self.graph(),
+            _ => return self.item(item),
+        };
+        screen
+    }
+
+    /// The history as a graph, labelled with the lowest and highest
+    /// point, or a title until the first point
+    fn graph(&self) -> Screen {
+        let Some((min, max)) = self.history.min_max() else {
+            let mut screen = Screen::new("No data");
+            let _ = screen.title.push_str(self.menu.item().title);
+            return screen;
+        };
+        let mut graph = Graph {
+            points: self.history.iter().collect(),
+            min,
+            max,
+            ..Graph::default()
+        };
+        let _ = write!(graph.min_label, "{:#}", Temperature::new(min, self.unit));
+        let _ = write!(graph.max_label, "{:#}", Temperature::new(max, self.unit));
+        Screen {
+            graph: Some(graph),
+            ..Screen::default()
+        }
+    }
+
+    /// The title above the field values, with the edited one underlined,
+    /// or the text, the title alone if there is neither
+    fn item(&self, item: &menu::Item) -> Screen {
+        let mut screen = Screen::default();
+        if !item.fields.is_empty() {
+            let (values, field) = match self.menu.edit() {
+                Some(edit) => (edit.values, Some(edit.field)),
+                None => (self.values, None),
+            };
+            screen.underline = item
+                .write_values(&values, field, &mut screen.body)
+                .ok()
+                .flatten();
+        } else if !item.text.is_empty() {
+            let _ = screen.body.push_str(item.text);
+        } else {
+            return Screen::new(item.title);
+        }
+        if let Some(parent) = item.parent {
+            let _ = write!(screen.title, "{} / ", menu::item(parent).title);
+        }
+        let _ = screen.title.push_str(item.title);
+        screen
+    }
+}
+
+#[cfg(test)]
+mod test_view {
+    use super::*;
+
+    fn select(view: &mut View, page: Page) {
+        while view.menu().page() != page {
+            let input = match view.menu().item().kind {
+                menu::Kind::Submenu => Input::Select,
+                _ => Input::Next,
+            };
+            view.input(input, &[0; 3]);
+        }
+    }
+
+    #[test]
+    fn test_live_values() {
+        let mut view = View::default();
+        view.set_temperature(21.3);
+        // the clock is shown, the temperature is only cached
+        assert!(view.set_time(23, 59, 1));
+        assert_eq!(view.screen(), Screen::new("23:59:01"));
+        view.input(Input::Next, &[0; 3]);
+        assert_eq!(view.screen(), Screen::new("Temp: 21.3 C"));
+        assert!(!view.set_time(0, 0, 0));
+        assert!(view.set_temperature(-3.0));
+        view.set_unit(TemperatureUnit::Fahrenheit);
+        assert_eq!(view.screen().body, "Temp: 26.6 F");
+        for _ in 0..4 {
+            view.input(Input::Next, &[0; 3]);
+        }
+        assert_eq!(view.screen(), Screen::new("00:00:00"));
+    }
+
+    #[test]
+    fn test_no_temperature() {
+        let mut view = View::default();
+        select(&mut view, Page::Temperature);
+        assert_eq!(view.screen().body, "Temp: --.- C");
+    }
+
+    #[test]
+    fn test_fault() {
+        let mut view = View::default();
+        select(&mut view, Page::Temperature);
+        view.set_temperature(21.0);
+        assert!(view.set_fault(SensorError::OpenCircuit));
+        // redrawn when the fault changes only
+        assert!(!view.set_fault(SensorError::OpenCircuit));
+        let screen = view.screen();
+        assert_eq!(screen.title, "Sensor fault");
+        assert_eq!(screen.body, "Open circuit");
+        assert!(view.set_fault(SensorError::ShortCircuit));
+        assert!(view.set_temperature(20.5));
+        assert_eq!(view.screen(), Screen::new("Temp: 20.5 C"));
+        // the history skips faulty readings
+        assert_eq!(view.history().len(), 0);
+    }
+
+    #[test]
+    fn test_alarm() {
+        let mut view = View::default();
+        let ringing = State::Ringing {
+            alarm: 0,
+            snoozes: 1,
+            elapsed: 0,
+        };
+        assert!(view.set_alarm(ringing));
+        assert!(!view.set_alarm(ringing));
+        // the clock is hidden by the alarm
+        assert!(!view.set_time(7, 0, 0));
+        assert_eq!(view.screen(), Screen::new("ALARM! Z1"));
+        view.set_alarm(State::Snoozed {
+            alarm: 0,
+            snoozes: 1,
+            remaining: 299,
+        });
+        assert_eq!(view.screen().body, "Z1 04:59");
+        assert!(view.set_alarm(State::Idle));
+        assert_eq!(view.screen().body, "07:00:00");
+    }
+
+    #[test]
+    fn test_history() {
+        let mut view = View::default();
+        select(&mut view, Page::History);
+        assert_eq!(view.screen().body, "No data");
+        assert_eq!(view.screen().title, "History");
+        // redrawn once per point
+        let redraws = (0..2 * HISTORY_PERIOD)
+            .filter(|i| view.set_temperature(if *i < HISTORY_PERIOD { 20.0 } else { 25.0 }))
+            .count();
+        assert_eq!(redraws, 2);
+        view.set_unit(TemperatureUnit::Kelvin);
+        let graph = view.screen().graph.unwrap();
+        assert_eq!(graph.points, [20.0, 25.0]);
+        assert_eq!((graph.min, graph.max), (20.0, 25.0));
+        assert_eq!(graph.min_label, "293.1K");
+        assert_eq!(graph.max_label, "298.1K");
+        assert_eq!(view.history().len(), 2);
+    }
+
+    #[test]
+    fn test_menu_pages() {
+        let mut view = View::default();
+        select(&mut view, Page::Alarms);
+        view.set_values(&[2, 0, 0]);
+        assert_eq!(view.screen().title, "Alarms");
+        assert_eq!(view.screen().body, "2 active");
+
+        select(&mut view, Page::Settings);
+        assert_eq!(view.screen(), Screen::new("Settings"));
+
+        select(&mut view, Page::Time);
+        view.set_values(&[7, 30, 0]);
+        let screen = view.screen();
+        assert_eq!(screen.title, "Settings / Time");
+        assert_eq!((screen.body.as_str(), screen.underline), ("07:30:00", None));
+        // editing shows the edited values, not the live ones
+        view.input(Input::Select, &[7, 30, 0]);
+        view.input(Input::Next, &[0; 3]);
+        view.input(Input::Up, &[0; 3]);
+        view.set_values(&[8, 0, 0]);
+        let screen = view.screen();
+        assert_eq!(
+            (screen.body.as_str(), screen.underline),
+            ("07:31:00", Some(3..5))
+        );
+        view.input(Input::Select, &[0; 3]);
+
+        select(&mut view, Page::About);
+        let screen = view.screen();
+        assert_eq!(screen.title, "Settings / About");
+        assert!(screen.body.starts_with('v'));
+
+        select(&mut view, Page::Back);
+        assert_eq!(view.screen(), Screen::new("< Back"));
+    }
+}
@@ -0,0 +1,403 @@
+//! Derive a command table
+//!
+//! `#[derive(Command)]` on an enum implements `command_parser::table::Commands`
+//! with a table of one `Spec` per variant, so the enum gets a `no_std`
+//! `parse_result` and `parse_diagnostic` with the same errors, diagnostics,
+//! usage lines and completion as the built-in commands.
+//!
+//! Attributes of a variant, `#[command(..)]`:
+//!
+//! - `keyword = "alarm add"`, the words of the command, by default the
+//!   variant name in kebab case (e.g., `FactoryReset` is `factory-reset`)
+//! - `aliases = ["alarm rm", ..]`
+//! - `help = ".."`, by default the doc comment of the variant
+//!
+//! Each field is an argument, in order. Unsigned and signed integers,
+//! `f32`, `bool` and `heapless::String<N>` are supported, an `Option` of
+//! them is an optional argument. Attributes of a field, `#[arg(..)]`:
+//!
+//! - `name = ".."`, by default the field name, or `value` for a tuple
+//!   variant
+//! - `range = min..=max` of a number, by default the range of the type
+//! - `unit = ".."`, e.g., `minutes`
+//! - `help = ".."`, by default the doc comment of the field
+//! - `rest`, a `String<N>` takes the rest of the line
+//! - `frequency`, an unsigned integer in Hz also takes `1k` or `2.5kHz`
+//! - `level`, an unsigned integer also takes a percentage of the maximum,
+//!   e.g., `50%`
+//! - `duration = "m"`, an unsigned integer in `ms`, `s`, `m` or `h` also
+//!   takes a duration in the other units, e.g., `300s`
+//! - `decimals = 2`, a signed integer or `f32` is parsed as a fixed-point
+//!   number with up to 2 decimals, an integer gets the number times 100,
+//!   which must fit the type, at most 9 decimals
+//!
+//! # Examples
+//! ```
+//! use command_parser::table::Commands;
+//! use command_parser::Error;
+//! use command_parser_derive::Command;
+//! use heapless::String;
+//!
+//! #[derive(Debug, PartialEq, Command)]
+//! enum Led {
+//!     /// turn the LED on
+//!     On,
+//!     Off,
+//!     #[command(keyword = "led blink", aliases = ["blink"])]
+//!     Blink {
+//!         #[arg(range = 10..=10_000, unit = "ms")]
+//!         period: u32,
+//!     },
+//!     Say(#[arg(rest)] String<32>),
+//! }
+//!
+//! assert_eq!(Led::parse_result(b"on"), Ok(Led::On));
+//! assert_eq!(Led::parse_result(b"blink 500"), Ok(Led::Blink { period: 500 }));
+//! assert_eq!(Led::parse_result(b"led blink 5"), Err(Error::ArgError));
+//! assert_eq!(Led::parse_result(b"off now"), Err(Error::ArgNumber));
+//! assert_eq!(
+//!     Led::parse_result(b"say hello world"),
+//!     Ok(Led::Say("hello world".parse().unwrap()))
+//! );
+//! assert_eq!(Led::TABLE[0].help, "turn the LED on");
+//! ```
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{quote, ToTokens};
+use syn::spanned::Spanned;
+use syn::{
+    parse_macro_input, Attribute, Data, DeriveInput, Expr, ExprArray, Fields, GenericArgument,
+    Ident, LitStr, PathArguments, RangeLimits, Type, Variant,
+};
+
+#[proc_macro_derive(Command, attributes(command, arg))]
+pub fn derive_command(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Enum(data) = &input.data else {
+        return Err(syn::Error::new(
+            input.ident.span(),
+            "Command can only be derived for enums",
+        ));
+    };
+    let specs = data
+        .variants
+        .iter()
+        .map(spec)
+        .collect::<syn::Result<Vec<_>>>()?;
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::command_parser::table::Commands for #ident #ty_generics #where_clause {
+            const TABLE: &'static [::command_parser::table::Spec<Self>] = &[#(#specs),*];
+        }
+    })
+}
+
+/// The doc comment, lines joined by spaces
+fn doc(attrs: &[Attribute]) -> String {
+    let lines = attrs.iter().filter_map(|attr| match &attr.meta {
+        syn::Meta::NameValue(meta) if meta.path.is_ident("doc") => match &meta.value {
+            Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(line),
+                ..
+            }) => Some(line.value().trim().to_string()),
+            _ => None,
+        },
+        _ => None,
+    });
+    lines.collect::<Vec<_>>().join(" ")
+}
+
+/// `FactoryReset` as `factory-reset`
+fn kebab_case(ident: &Ident) -> String {
+    let mut out = String::new();
+    for (i, c) in ident.to_string().chars().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            out.push('-');
+        }
+        out.extend(c.to_lowercase());
+    }
+    out
+}
+
+/// The `Spec` of a variant
+fn spec(variant: &Variant) -> syn::Result<TokenStream2> {
+    let mut keyword = kebab_case(&variant.ident);
+    let mut aliases = Vec::new();
+    let mut help = doc(&variant.attrs);
+    for attr in variant
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("command"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("keyword") {
+                keyword = meta.value()?.parse::<LitStr>()?.value();
+            } else if meta.path.is_ident("help") {
+                help = meta.value()?.parse::<LitStr>()?.value();
+            } else if meta.path.is_ident("aliases") {
+                let array: ExprArray = meta.value()?.parse()?;
+                for alias in array.elems {
+                    aliases.push(syn::parse2::<LitStr>(alias.into_token_stream())?);
+                }
+            } else {
+                return Err(meta.error("expected keyword, aliases or help"));
+            }
+            Ok(())
+        })?;
+    }
+    if keyword.split_whitespace().next().is_none() {
+        return Err(syn::Error::new(variant.ident.span(), "empty keyword"));
+    }
+
+    let args = variant
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| arg(i, field))
+        .collect::<syn::Result<Vec<_>>>()?;
+    let specs = args.iter().map(|(spec, _)| spec);
+    let values = args.iter().map(|(_, value)| value);
+    let ident = &variant.ident;
+    let command = match &variant.fields {
+        Fields::Unit => quote!(Self::#ident),
+        Fields::Unnamed(_) => quote!(Self::#ident(#(#values),*)),
+        Fields::Named(fields) => {
+            let names = fields.named.iter().map(|field| &field.ident);
+            quote!(Self::#ident { #(#names: #values),* })
+        }
+    };
+    let values = if args.is_empty() {
+        quote!(_)
+    } else {
+        quote!(values)
+    };
+    Ok(quote! {
+        ::command_parser::table::Spec::new(#keyword, #help, |#values| {
+            ::core::result::Result::Ok(#command)
+        })
+        .aliases(&[#(#aliases),*])
+        .args(&[#(#specs),*])
+    })
+}
+
+/// The argument types
+enum Ty {
+    Uint(Ident),
+    Int(Ident),
+    Float,
+    Bool,
+    /// `String<N>` with the tokens of `N`
+    Text(TokenStream2),
+}
+
+/// The type of a field and whether it is an `Option`
+fn ty(ty: &Type) -> syn::Result<(Ty, bool)> {
+    let unsupported = || {
+        syn::Error::new(
+            ty.span(),
+            "expected an integer, f32, bool, heapless::String<N> or an Option of them",
+        )
+    };
+    let Type::Path(path) = ty else {
+        return Err(unsupported());
+    };
+    let segment = path.path.segments.last().ok_or_else(unsupported)?;
+    let generic = match &segment.arguments {
+        PathArguments::AngleBracketed(args) if args.args.len() == 1 => args.args.first(),
+        _ => None,
+    };
+    let name = segment.ident.to_string();
+    match (name.as_str(), generic) {
+        ("u8" | "u16" | "u32", None) => Ok((Ty::Uint(segment.ident.clone()), false)),
+        ("i8" | "i16" | "i32", None) => Ok((Ty::Int(segment.ident.clone()), false)),
+        ("f32", None) => Ok((Ty::Float, false)),
+        ("bool", None) => Ok((Ty::Bool, false)),
+        ("String", Some(GenericArgument::Const(len))) => Ok((Ty::Text(quote!(#len)), false)),
+        ("String", Some(GenericArgument::Type(len))) => Ok((Ty::Text(quote!(#len)), false)),
+        ("Option", Some(GenericArgument::Type(inner))) => match self::ty(inner)? {
+            (_, true) => Err(unsupported()),
+            (inner, false) => Ok((inner, true)),
+        },
+        _ => Err(unsupported()),
+    }
+}
+
+/// How a number is parsed, other than plain
+enum Parser {
+    Plain,
+    Frequency,
+    Level,
+    /// The `TimeUnit` variant
+    Duration(Ident),
+    Decimals(u32),
+}
+
+/// The `Arg` of field `i` and the expression of its value from `values`
+fn arg(i: usize, field: &syn::Field) -> syn::Result<(TokenStream2, TokenStream2)> {
+    let mut name = match &field.ident {
+        Some(ident) => ident.to_string(),
+        None if i == 0 => "value".to_string(),
+        None => format!("value{}", i + 1),
+    };
+    let mut range = None;
+    let mut unit = String::new();
+    let mut help = doc(&field.attrs);
+    let mut rest = false;
+    let mut parser = Parser::Plain;
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("arg"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                name = meta.value()?.parse::<LitStr>()?.value();
+            } else if meta.path.is_ident("range") {
+                match meta.value()?.parse::<Expr>()? {
+                    Expr::Range(syn::ExprRange {
+                        start: Some(start),
+                        limits: RangeLimits::Closed(_),
+                        end: Some(end),
+                        ..
+                    }) => range = Some((start, end)),
+                    expr => return Err(syn::Error::new(expr.span(), "expected min..=max")),
+                }
+            } else if meta.path.is_ident("unit") {
+                unit = meta.value()?.parse::<LitStr>()?.value();
+            } else if meta.path.is_ident("help") {
+                help = meta.value()?.parse::<LitStr>()?.value();
+            } else if meta.path.is_ident("rest") {
+                rest = true;
+            } else if meta.path.is_ident("frequency") {
+                parser = Parser::Frequency;
+            } else if meta.path.is_ident("level") {
+                parser = Parser::Level;
+            } else if meta.path.is_ident("duration") {
+                let unit = meta.value()?.parse::<LitStr>()?;
+                let variant = match unit.value().as_str() {
+                    "ms" => "Millis",
+                    "s" => "Seconds",
+                    "m" => "Minutes",
+                    "h" => "Hours",
+                    _ => return Err(syn::Error::new(unit.span(), "expected ms, s, m or h")),
+                };
+                parser = Parser::Duration(Ident::new(variant, unit.span()));
+            } else if meta.path.is_ident("decimals") {
+                parser = Parser::Decimals(meta.value()?.parse::<syn::LitInt>()?.base10_parse()?);
+            } else {
+                return Err(meta.error(
+                    "expected name, range, unit, help, rest, frequency, level, duration or \
+                     decimals",
+                ));
+            }
+            Ok(())
+        })?;
+    }
+
+    let (ty, optional) = ty(&field.ty)?;
+    let kinds = quote!(::command_parser::table::Kind);
+    let error = quote!(::command_parser::Error::ArgError);
+    let (min, max) = match (&ty, &range) {
+        (_, Some((min, max))) => (quote!(#min), quote!(#max)),
+        (Ty::Uint(ty) | Ty::Int(ty), None) => (quote!(#ty::MIN), quote!(#ty::MAX)),
+        (_, None) => (quote!(f32::MIN), quote!(f32::MAX)),
+    };
+    let (kind, value) = match (&ty, &parser) {
+        (Ty::Text(_) | Ty::Bool, _) if range.is_some() => {
+            return Err(syn::Error::new(field.span(), "a range is only for numbers"))
+        }
+        (Ty::Uint(_) | Ty::Int(_) | Ty::Float | Ty::Bool, _) if rest => {
+            return Err(syn::Error::new(field.span(), "rest is only for strings"))
+        }
+        (Ty::Uint(ty), Parser::Plain) => {
+            let kind = quote!(#kinds::Uint { min: (#min) as u32, max: (#max) as u32 });
+            (kind, (quote!(uint), quote!(|v| v as #ty)))
+        }
+        (Ty::Uint(ty), Parser::Frequency) => {
+            let kind = quote!(#kinds::Frequency { min: (#min) as u32, max: (#max) as u32 });
+            (kind, (quote!(uint), quote!(|v| v as #ty)))
+        }
+        (Ty::Uint(ty), Parser::Level) => {
+            let kind = quote!(#kinds::Level { max: (#max) as u32 });
+            (kind, (quote!(uint), quote!(|v| v as #ty)))
+        }
+        (Ty::Uint(ty), Parser::Duration(unit)) => {
+            let kind = quote!(#kinds::Duration {
+                min: (#min) as u32,
+                max: (#max) as u32,
+                unit: ::command_parser::args::TimeUnit::#unit,
+            });
+            (kind, (quote!(uint), quote!(|v| v as #ty)))
+        }
+        (Ty::Int(ty), Parser::Plain) => {
+            let kind = quote!(#kinds::Int { min: (#min) as i32, max: (#max) as i32 });
+            (kind, (quote!(int), quote!(|v| v as #ty)))
+        }
+        (Ty::Float, Parser::Plain) => {
+            let kind = quote!(#kinds::Float { min: (#min) as f32, max: (#max) as f32 });
+            (kind, (quote!(float), quote!(|v| v)))
+        }
+        (Ty::Int(_) | Ty::Float, Parser::Decimals(decimals)) => {
+            let scale = 10i32
+                .checked_pow(*decimals)
+                .ok_or_else(|| syn::Error::new(field.span(), "decimals is at most 9"))?;
+            // the range is in whole units, the value in 10^-decimals fits
+            // the type, the float casts saturate
+            let int = match &ty {
+                Ty::Int(ty) => quote!(#ty),
+                _ => quote!(i32),
+            };
+            let (min, max) = match range {
+                Some(_) => (
+                    quote!(((#min) as f64 * #scale as f64) as #int as i32),
+                    quote!(((#max) as f64 * #scale as f64) as #int as i32),
+                ),
+                None => (quote!(#int::MIN as i32), quote!(#int::MAX as i32)),
+            };
+            let decimals = *decimals as u8;
+            let kind = quote!(#kinds::Fixed { min: #min, max: #max, decimals: #decimals });
+            let convert = match &ty {
+                Ty::Int(ty) => quote!(|v| v as #ty),
+                _ => quote!(|v| v as f32 / #scale as f32),
+            };
+            (kind, (quote!(fixed), convert))
+        }
+        (Ty::Bool, Parser::Plain) => (quote!(#kinds::Bool), (quote!(bool), quote!(|v| v))),
+        (Ty::Text(len), Parser::Plain) => {
+            let kind = match rest {
+                true => quote!(#kinds::Rest { len: #len }),
+                false => quote!(#kinds::Text { len: #len }),
+            };
+            let parse = quote!(|v: &str| v.parse().map_err(|_| #error));
+            (kind, (quote!(text), parse))
+        }
+        _ => {
+            return Err(syn::Error::new(
+                field.span(),
+                "frequency, level and duration are only for unsigned integers, decimals for \
+                 signed integers and f32",
+            ))
+        }
+    };
+
+    let (getter, convert) = value;
+    let value = match (&ty, optional) {
+        (Ty::Text(_), false) => quote!((#convert)(values.#getter(#i)?)?),
+        (Ty::Text(_), true) => quote!(values.#getter(#i).ok().map(#convert).transpose()?),
+        (_, false) => quote!((#convert)(values.#getter(#i)?)),
+        (_, true) => quote!(values.#getter(#i).ok().map(#convert)),
+    };
+    let optional = optional.then(|| quote!(.optional()));
+    let unit = (!unit.is_empty()).then(|| quote!(.unit(#unit)));
+    let help = (!help.is_empty()).then(|| quote!(.help(#help)));
+    let spec = quote!(::command_parser::table::Arg::new(#name, #kind) #unit #optional #help);
+    Ok((spec, value))
+}
@@ -0,0 +1,186 @@
+use command_parser::table::Commands;
+use command_parser::{parse_diagnostic, parse_result, Error};
+use command_parser_derive::Command;
+use heapless::String;
+
+/// Some of the built-in commands, derived
+#[derive(Debug, PartialEq, Command)]
+enum Derived {
+    #[command(keyword = "freq")]
+    FrequencyHz(#[arg(name = "hz", frequency)] u32),
+    Duty(#[arg(name = "duty", level)] u8),
+    /// start the tone
+    Start,
+    Stop,
+    #[command(keyword = "tz")]
+    TimeZone(#[arg(name = "offset", range = -720..=840, unit = "minutes")] i16),
+    #[command(keyword = "alarm del", aliases = ["alarm rm"])]
+    AlarmDel {
+        /// the alarm to delete
+        index: u8,
+    },
+    #[command(keyword = "snooze")]
+    SnoozeMinutes(#[arg(name = "minutes", range = 1..=255, duration = "m")] u8),
+    Play(#[arg(name = "name")] String<16>),
+    #[command(keyword = "cal add")]
+    CalAdd(#[arg(name = "celsius", range = -40..=125, decimals = 2, unit = "C")] f32),
+    FactoryReset,
+    #[command(help = "play a note")]
+    Note {
+        hz: u16,
+        ms: Option<u16>,
+    },
+    Say(#[arg(rest)] String<32>),
+    Mute(bool),
+    Offset(#[arg(range = -10..=10, decimals = 1)] i16),
+    Trim(#[arg(decimals = 1)] i8),
+    Gain(#[arg(range = -100..=100, decimals = 1)] i8),
+}
+
+const LINES: &[&[u8]] = &[
+    b"freq 100",
+    b"freq",
+    b"freq x",
+    b"freq 1 2",
+    b"duty 255",
+    b"duty 256",
+    b"duty 50%",
+    b"duty 0x80",
+    b"freq 2.5kHz",
+    b"freq 2.5GHz",
+    b"snooze 5m",
+    b"snooze 90s",
+    b"start",
+    b"  stop  now",
+    b"tz -721",
+    b"tz +60",
+    b"alarm del 3",
+    b"alarm rm x",
+    b"alarm",
+    b"snooze 0",
+    b"play nokia",
+    b"play nokia tetris",
+    b"play a_name_that_is_too_long",
+    b"cal add 125.1",
+    b"cal add -12.5",
+    b"cal add 25.125",
+    b"factory-reset",
+    b"bogus",
+    b"",
+    b"duty \xff",
+];
+
+#[test]
+fn test_same_errors() {
+    for line in LINES {
+        assert_eq!(
+            Derived::parse_result(line).err(),
+            parse_result(line).err(),
+            "{:?}",
+            core::str::from_utf8(line)
+        );
+    }
+}
+
+#[test]
+fn test_same_diagnostics() {
+    for line in LINES {
+        let derived = Derived::parse_diagnostic(line).err().map(|d| d.to_string());
+        let built_in = parse_diagnostic(line).err().map(|d| d.to_string());
+        // the derived table has fewer alarm subcommands
+        if !line.starts_with(b"alarm") {
+            assert_eq!(derived, built_in, "{:?}", core::str::from_utf8(line));
+        }
+    }
+}
+
+#[test]
+fn test_commands() {
+    assert_eq!(
+        Derived::parse_result(b"freq 440"),
+        Ok(Derived::FrequencyHz(440))
+    );
+    assert_eq!(
+        Derived::parse_result(b"tz -330"),
+        Ok(Derived::TimeZone(-330))
+    );
+    assert_eq!(
+        Derived::parse_result(b"alarm rm 2"),
+        Ok(Derived::AlarmDel { index: 2 })
+    );
+    assert_eq!(
+        Derived::parse_result(b"cal add 21.5"),
+        Ok(Derived::CalAdd(21.5))
+    );
+    assert_eq!(
+        Derived::parse_result(b"factory-reset"),
+        Ok(Derived::FactoryReset)
+    );
+    assert_eq!(
+        Derived::parse_result(b"say  hello  world "),
+        Ok(Derived::Say("hello  world".parse().unwrap()))
+    );
+}
+
+#[test]
+fn test_optional() {
+    assert_eq!(
+        Derived::parse_result(b"note 440"),
+        Ok(Derived::Note { hz: 440, ms: None })
+    );
+    assert_eq!(
+        Derived::parse_result(b"note 440 250"),
+        Ok(Derived::Note {
+            hz: 440,
+            ms: Some(250)
+        })
+    );
+    assert_eq!(
+        Derived::parse_result(b"note 440 70000"),
+        Err(Error::ArgError)
+    );
+    assert_eq!(Derived::parse_result(b"note"), Err(Error::ArgMissing));
+}
+
+#[test]
+fn test_rich_args() {
+    assert_eq!(Derived::parse_result(b"duty 50%"), Ok(Derived::Duty(128)));
+    assert_eq!(
+        Derived::parse_result(b"snooze 1h"),
+        Ok(Derived::SnoozeMinutes(60))
+    );
+    assert_eq!(Derived::parse_result(b"mute on"), Ok(Derived::Mute(true)));
+    assert_eq!(Derived::parse_result(b"mute 0"), Ok(Derived::Mute(false)));
+    assert_eq!(Derived::parse_result(b"mute x"), Err(Error::ArgError));
+    assert_eq!(
+        Derived::parse_result(b"offset -2.5"),
+        Ok(Derived::Offset(-25))
+    );
+    assert_eq!(Derived::parse_result(b"offset 10.5"), Err(Error::ArgError));
+    // the value in tenths fits the type
+    assert_eq!(
+        Derived::parse_result(b"trim -12.8"),
+        Ok(Derived::Trim(-128))
+    );
+    assert_eq!(Derived::parse_result(b"trim 12.7"), Ok(Derived::Trim(127)));
+    assert_eq!(Derived::parse_result(b"trim 100"), Err(Error::ArgError));
+    assert_eq!(Derived::parse_result(b"gain 12.7"), Ok(Derived::Gain(127)));
+    assert_eq!(Derived::parse_result(b"gain 12.8"), Err(Error::ArgError));
+    assert_eq!(Derived::parse_result(b"gain -100"), Err(Error::ArgError));
+}
+
+#[test]
+fn test_table() {
+    let find = |name| {
+        Derived::TABLE
+            .iter()
+            .find(|spec| spec.name == name)
+            .unwrap()
+    };
+    assert_eq!(find("start").help, "start the tone");
+    assert_eq!(find("stop").help, "");
+    assert_eq!(find("note").help, "play a note");
+    assert_eq!(find("alarm del").args[0].help, "the alarm to delete");
+    assert_eq!(find("note").usage().to_string(), "note <hz> [ms]");
+    assert_eq!(find("tz").args[0].to_string(), "-720..=840 minutes");
+}
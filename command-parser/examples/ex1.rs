@@ -1,10 +1,26 @@
-use command_parser::{parse, parse_result};
+use command_parser::{parse, parse_diagnostic, parse_result, COMMANDS};
 
 fn parse_str(s: &str) {
     println!("\ninput        {}", s);
     let s = s.as_bytes();
     println!("parse        {:?}", parse(s));
     println!("parse_result {:?}", parse_result(s));
+    if let Err(diagnostic) = parse_diagnostic(s) {
+        print!("{}", diagnostic);
+        let mut hint = String::new();
+        COMMANDS.write_hint(&mut hint, &diagnostic).unwrap();
+        print!("{}", hint);
+    }
+}
+
+fn complete(s: &str) {
+    println!("\ncomplete     {:?} -> {:?}", s, COMMANDS.complete(s));
+}
+
+fn help(s: &str) {
+    let mut help = String::new();
+    COMMANDS.write_help(&mut help, s).unwrap();
+    print!("\nhelp {}\n{}", s, help);
 }
 
 fn main() {
@@ -15,4 +31,47 @@ fn main() {
     parse_str("freq ");
     parse_str("freq    1000");
     parse_str("start  bogus");
+    parse_str("time");
+    parse_str("time 07:30:00");
+    parse_str("time 24:00:00");
+    parse_str("date 2026-10-18");
+    parse_str("date 2026-02-29");
+    parse_str("alarm add 07:30 weekdays work");
+    parse_str("alarm add 25:00");
+    parse_str("alarm list");
+    parse_str("alarm del 2");
+    parse_str("snooze 10");
+    parse_str("play nokia");
+    parse_str("melody beep:d=4,o=5,b=120:c,e,g,2c6");
+    parse_str("melody beep:d=4,o=5,b=120:c,x");
+    parse_str("tz -330");
+    parse_str("save");
+    parse_str("load");
+    parse_str("factory-reset");
+    parse_str("temp history");
+    parse_str("cal add 25");
+    parse_str("cal add 200");
+    parse_str("cal add 25.5");
+    parse_str("duty 50%");
+    parse_str("duty 0x80");
+    parse_str("duty 120%");
+    parse_str("snooze 5m");
+    parse_str("snooze 90s");
+    parse_str("freq 2.5kHz");
+    parse_str("freq 2.5GHz");
+    parse_str("cal fit");
+    parse_str("sensors");
+    parse_str("units f");
+    parse_str("alarm rm 2");
+    parse_str("alarm snooze");
+    complete("al");
+    complete("alarm d");
+    complete("units ");
+    complete("help al");
+    parse_str("help duty");
+    parse_str("snooze");
+    parse_str("stop now");
+    help("");
+    help("alarm");
+    help("snooze");
 }
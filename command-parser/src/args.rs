@@ -0,0 +1,473 @@
+//! Argument parsers
+//!
+//! Integers in decimal or `0x` hex, fixed-point decimals, durations with a
+//! unit (`500ms`, `2s`, `5m`), frequencies with a multiplier (`1k`,
+//! `2.5kHz`), percentages (`50%`) and booleans (`on`, `off`). The numbers
+//! are parsed into integers without floating point, and an invalid argument
+//! tells why with an `Invalid`, e.g., `TooHigh` rather than just
+//! `Error::ArgError`.
+
+use core::fmt;
+
+use crate::Error;
+
+/// Why an argument is invalid
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Invalid {
+    /// Not a number, or not one of the words
+    Syntax,
+    /// Below the minimum of the argument
+    TooLow,
+    /// Above the maximum of the argument
+    TooHigh,
+    /// Finer than the argument takes, e.g., `2.55` for tenths or `90s` for
+    /// minutes
+    Precision,
+    /// An unknown unit, e.g., `5d` for a duration
+    Unit,
+    /// Longer than the argument takes
+    TooLong,
+    /// Hour, minute or second out of range
+    Time,
+    /// Year, month or day out of range
+    Date,
+}
+
+impl From<Invalid> for Error {
+    fn from(invalid: Invalid) -> Self {
+        match invalid {
+            Invalid::Time => Error::InvalidTime,
+            Invalid::Date => Error::InvalidDate,
+            _ => Error::ArgError,
+        }
+    }
+}
+
+impl fmt::Display for Invalid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Invalid::Syntax => "invalid argument",
+            Invalid::TooLow => "too low",
+            Invalid::TooHigh => "too high",
+            Invalid::Precision => "too precise",
+            Invalid::Unit => "unknown unit",
+            Invalid::TooLong => "too long",
+            Invalid::Time => "invalid time",
+            Invalid::Date => "invalid date",
+        })
+    }
+}
+
+/// Check that `value` is in `min..=max`
+pub fn in_range<T: PartialOrd>(value: T, min: T, max: T) -> Result<T, Invalid> {
+    match value {
+        value if value < min => Err(Invalid::TooLow),
+        value if value > max => Err(Invalid::TooHigh),
+        value => Ok(value),
+    }
+}
+
+/// Split a leading sign off `arg`, true if negative
+fn sign(arg: &str) -> (bool, &str) {
+    match arg.as_bytes().first() {
+        Some(b'-') => (true, &arg[1..]),
+        Some(b'+') => (false, &arg[1..]),
+        _ => (false, arg),
+    }
+}
+
+/// Parse digits in `radix`, `TooHigh` if they overflow
+fn digits(arg: &str, radix: u32) -> Result<u64, Invalid> {
+    if arg.is_empty() {
+        return Err(Invalid::Syntax);
+    }
+    arg.chars().try_fold(0u64, |value, c| {
+        let digit = c.to_digit(radix).ok_or(Invalid::Syntax)?;
+        value
+            .checked_mul(radix as u64)
+            .and_then(|value| value.checked_add(digit as u64))
+            .ok_or(Invalid::TooHigh)
+    })
+}
+
+/// Parse decimal digits, or hex digits with a `0x` prefix
+fn magnitude(arg: &str) -> Result<u64, Invalid> {
+    match arg.strip_prefix("0x").or_else(|| arg.strip_prefix("0X")) {
+        Some(hex) => digits(hex, 16),
+        None => digits(arg, 10),
+    }
+}
+
+/// Parse a decimal, or hex with a `0x` prefix, unsigned integer
+///
+/// # Examples
+/// ```
+/// use command_parser::args::*;
+/// assert_eq!(uint("255"), Ok(255));
+/// assert_eq!(uint("0xff"), Ok(255));
+/// assert_eq!(uint("4294967296"), Err(Invalid::TooHigh));
+/// assert_eq!(uint("0xfg"), Err(Invalid::Syntax));
+/// ```
+pub fn uint(arg: &str) -> Result<u32, Invalid> {
+    let (negative, arg) = sign(arg);
+    match (negative, magnitude(arg)?) {
+        (true, 0) => Ok(0),
+        (true, _) => Err(Invalid::TooLow),
+        (false, value) => u32::try_from(value).map_err(|_| Invalid::TooHigh),
+    }
+}
+
+/// Parse a signed integer, decimal or hex with a `0x` prefix after the sign
+///
+/// # Examples
+/// ```
+/// use command_parser::args::*;
+/// assert_eq!(int("-330"), Ok(-330));
+/// assert_eq!(int("-0x10"), Ok(-16));
+/// assert_eq!(int("+60"), Ok(60));
+/// ```
+pub fn int(arg: &str) -> Result<i32, Invalid> {
+    let (negative, rest) = sign(arg);
+    let value = i64::try_from(magnitude(rest)?).map_err(|_| Invalid::TooHigh)?;
+    let value = if negative { -value } else { value };
+    i32::try_from(value).map_err(|_| match negative {
+        true => Invalid::TooLow,
+        false => Invalid::TooHigh,
+    })
+}
+
+/// Parse a signed decimal into an integer with `decimals` implied
+/// decimals, without floating point
+///
+/// # Examples
+/// ```
+/// use command_parser::args::*;
+/// assert_eq!(fixed("25.5", 1), Ok(255));
+/// assert_eq!(fixed("-12", 2), Ok(-1200));
+/// assert_eq!(fixed("25.50", 1), Ok(255));
+/// assert_eq!(fixed("25.55", 1), Err(Invalid::Precision));
+/// ```
+pub fn fixed(arg: &str, decimals: u8) -> Result<i32, Invalid> {
+    let (negative, rest) = sign(arg);
+    let out_of_range = match negative {
+        true => Invalid::TooLow,
+        false => Invalid::TooHigh,
+    };
+    let scale = 10u64.checked_pow(decimals as u32).ok_or(out_of_range)?;
+    let value = i64::try_from(decimal(rest, scale)?).map_err(|_| out_of_range)?;
+    let value = match negative {
+        true => value.checked_neg().ok_or(out_of_range)?,
+        false => value,
+    };
+    i32::try_from(value).map_err(|_| out_of_range)
+}
+
+/// Parse an unsigned decimal, e.g., `2.5`, multiplied by `scale`, which
+/// must give an integer
+fn decimal(arg: &str, scale: u64) -> Result<u64, Invalid> {
+    let (int, fraction) = arg.split_once('.').unwrap_or((arg, ""));
+    if int.is_empty() && fraction.is_empty() {
+        return Err(Invalid::Syntax);
+    }
+    let int = match int {
+        "" => 0,
+        int => digits(int, 10)?,
+    };
+    let value = int.checked_mul(scale).ok_or(Invalid::TooHigh)?;
+    // the fraction digit by digit, the digits past the powers of ten in
+    // scale must be zeros
+    let mut place = scale;
+    let mut fraction_value = 0;
+    for c in fraction.chars() {
+        let digit = c.to_digit(10).ok_or(Invalid::Syntax)? as u64;
+        if !place.is_multiple_of(10) {
+            if digit != 0 {
+                return Err(Invalid::Precision);
+            }
+            continue;
+        }
+        place /= 10;
+        fraction_value += digit * place;
+    }
+    value.checked_add(fraction_value).ok_or(Invalid::TooHigh)
+}
+
+/// Split `arg` into the number and the unit following it
+fn unit(arg: &str) -> (&str, &str) {
+    let i = arg
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(arg.len());
+    arg.split_at(i)
+}
+
+/// Unit of a duration
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimeUnit {
+    Millis,
+    Seconds,
+    Minutes,
+    Hours,
+}
+
+impl TimeUnit {
+    pub fn millis(self) -> u64 {
+        match self {
+            TimeUnit::Millis => 1,
+            TimeUnit::Seconds => 1000,
+            TimeUnit::Minutes => 60_000,
+            TimeUnit::Hours => 3_600_000,
+        }
+    }
+
+    /// The suffix, e.g., `m`
+    pub fn symbol(self) -> &'static str {
+        match self {
+            TimeUnit::Millis => "ms",
+            TimeUnit::Seconds => "s",
+            TimeUnit::Minutes => "m",
+            TimeUnit::Hours => "h",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TimeUnit::Millis => "milliseconds",
+            TimeUnit::Seconds => "seconds",
+            TimeUnit::Minutes => "minutes",
+            TimeUnit::Hours => "hours",
+        }
+    }
+
+    fn from_symbol(symbol: &str) -> Option<Self> {
+        [
+            TimeUnit::Millis,
+            TimeUnit::Seconds,
+            TimeUnit::Minutes,
+            TimeUnit::Hours,
+        ]
+        .into_iter()
+        .find(|unit| unit.symbol() == symbol)
+    }
+}
+
+/// Parse a duration, `500ms`, `2s`, `1.5m` or `1h`, into a whole number
+/// of `unit`, which is also the unit of a bare number
+///
+/// # Examples
+/// ```
+/// use command_parser::args::*;
+/// assert_eq!(duration("5m", TimeUnit::Minutes), Ok(5));
+/// assert_eq!(duration("300s", TimeUnit::Minutes), Ok(5));
+/// assert_eq!(duration("10", TimeUnit::Minutes), Ok(10));
+/// assert_eq!(duration("1.5s", TimeUnit::Millis), Ok(1500));
+/// assert_eq!(duration("90s", TimeUnit::Minutes), Err(Invalid::Precision));
+/// assert_eq!(duration("5d", TimeUnit::Minutes), Err(Invalid::Unit));
+/// ```
+pub fn duration(arg: &str, unit: TimeUnit) -> Result<u32, Invalid> {
+    if arg.starts_with('-') {
+        return Err(Invalid::TooLow);
+    }
+    let (number, symbol) = self::unit(arg.strip_prefix('+').unwrap_or(arg));
+    let given = match symbol {
+        "" => unit,
+        symbol => TimeUnit::from_symbol(symbol).ok_or(Invalid::Unit)?,
+    };
+    let millis = decimal(number, given.millis())?;
+    if !millis.is_multiple_of(unit.millis()) {
+        return Err(Invalid::Precision);
+    }
+    u32::try_from(millis / unit.millis()).map_err(|_| Invalid::TooHigh)
+}
+
+/// Parse a frequency in Hz, `440`, `440Hz`, `1k`, `2.5kHz` or `1M`
+///
+/// # Examples
+/// ```
+/// use command_parser::args::*;
+/// assert_eq!(frequency("440"), Ok(440));
+/// assert_eq!(frequency("1k"), Ok(1000));
+/// assert_eq!(frequency("2.5kHz"), Ok(2500));
+/// assert_eq!(frequency("0.5Hz"), Err(Invalid::Precision));
+/// ```
+pub fn frequency(arg: &str) -> Result<u32, Invalid> {
+    if arg.starts_with('-') {
+        return Err(Invalid::TooLow);
+    }
+    let (number, symbol) = unit(arg.strip_prefix('+').unwrap_or(arg));
+    let scale = match symbol.strip_suffix("Hz").unwrap_or(symbol) {
+        "" => 1,
+        "k" => 1000,
+        "M" => 1_000_000,
+        _ => return Err(Invalid::Unit),
+    };
+    u32::try_from(decimal(number, scale)?).map_err(|_| Invalid::TooHigh)
+}
+
+/// Parse a percentage, e.g., `50%` or `12.5%`, in tenths of a percent
+///
+/// # Examples
+/// ```
+/// use command_parser::args::*;
+/// assert_eq!(percent("50%"), Ok(500));
+/// assert_eq!(percent("12.5%"), Ok(125));
+/// assert_eq!(percent("50"), Err(Invalid::Syntax));
+/// ```
+pub fn percent(arg: &str) -> Result<u32, Invalid> {
+    if arg.starts_with('-') {
+        return Err(Invalid::TooLow);
+    }
+    let number = arg.strip_suffix('%').ok_or(Invalid::Syntax)?;
+    u32::try_from(decimal(number, 10)?).map_err(|_| Invalid::TooHigh)
+}
+
+/// Parse `on`, `off`, `true`, `false`, `yes`, `no`, `1` or `0`
+///
+/// # Examples
+/// ```
+/// use command_parser::args::*;
+/// assert_eq!(boolean("on"), Ok(true));
+/// assert_eq!(boolean("0"), Ok(false));
+/// assert_eq!(boolean("maybe"), Err(Invalid::Syntax));
+/// ```
+pub fn boolean(arg: &str) -> Result<bool, Invalid> {
+    match arg {
+        "on" | "true" | "yes" | "1" => Ok(true),
+        "off" | "false" | "no" | "0" => Ok(false),
+        _ => Err(Invalid::Syntax),
+    }
+}
+
+/// Write `value` with `decimals` implied decimals, without trailing zeros
+pub(crate) fn write_fixed(f: &mut impl fmt::Write, value: i32, decimals: u8) -> fmt::Result {
+    let scale = 10u32.pow(decimals as u32);
+    let sign = if value < 0 { "-" } else { "" };
+    let (int, mut fraction) = (value.unsigned_abs() / scale, value.unsigned_abs() % scale);
+    write!(f, "{}{}", sign, int)?;
+    let mut decimals = decimals as usize;
+    while fraction != 0 && fraction.is_multiple_of(10) {
+        fraction /= 10;
+        decimals -= 1;
+    }
+    match fraction {
+        0 => Ok(()),
+        _ => write!(f, ".{:0width$}", fraction, width = decimals),
+    }
+}
+
+/// Write a frequency with a `k` or `M` multiplier where it is exact
+pub(crate) fn write_hz(f: &mut impl fmt::Write, hz: u32) -> fmt::Result {
+    match hz {
+        hz if hz >= 1_000_000 && hz.is_multiple_of(1_000_000) => write!(f, "{}MHz", hz / 1_000_000),
+        hz if hz >= 1000 && hz.is_multiple_of(1000) => write!(f, "{}kHz", hz / 1000),
+        hz => write!(f, "{}Hz", hz),
+    }
+}
+
+#[cfg(test)]
+mod test_args {
+    use super::*;
+
+    #[test]
+    fn test_uint() {
+        assert_eq!(uint("0"), Ok(0));
+        assert_eq!(uint("+7"), Ok(7));
+        assert_eq!(uint("0XFF"), Ok(255));
+        assert_eq!(uint("4294967295"), Ok(u32::MAX));
+        assert_eq!(uint("99999999999999999999999"), Err(Invalid::TooHigh));
+        assert_eq!(uint("-1"), Err(Invalid::TooLow));
+        assert_eq!(uint("0x"), Err(Invalid::Syntax));
+        assert_eq!(uint(""), Err(Invalid::Syntax));
+        assert_eq!(uint("1_000"), Err(Invalid::Syntax));
+    }
+
+    #[test]
+    fn test_int() {
+        assert_eq!(int("-2147483648"), Ok(i32::MIN));
+        assert_eq!(int("-2147483649"), Err(Invalid::TooLow));
+        assert_eq!(int("2147483648"), Err(Invalid::TooHigh));
+        assert_eq!(int("--1"), Err(Invalid::Syntax));
+        assert_eq!(int("-"), Err(Invalid::Syntax));
+    }
+
+    #[test]
+    fn test_fixed() {
+        assert_eq!(fixed("0.05", 2), Ok(5));
+        assert_eq!(fixed(".5", 1), Ok(5));
+        assert_eq!(fixed("5.", 1), Ok(50));
+        assert_eq!(fixed("-40", 2), Ok(-4000));
+        assert_eq!(fixed("125.001", 2), Err(Invalid::Precision));
+        assert_eq!(fixed("1.5", 0), Err(Invalid::Precision));
+        assert_eq!(fixed(".", 1), Err(Invalid::Syntax));
+        assert_eq!(fixed("1.2.3", 2), Err(Invalid::Syntax));
+        assert_eq!(fixed("NaN", 2), Err(Invalid::Syntax));
+        assert_eq!(fixed("30000000", 2), Err(Invalid::TooHigh));
+        assert_eq!(fixed("18446744073709551615", 0), Err(Invalid::TooHigh));
+        assert_eq!(fixed("-18446744073709551615", 0), Err(Invalid::TooLow));
+        assert_eq!(fixed("-92233720368547758.08", 2), Err(Invalid::TooLow));
+        assert_eq!(fixed("1", 20), Err(Invalid::TooHigh));
+        assert_eq!(fixed("-1", 255), Err(Invalid::TooLow));
+    }
+
+    #[test]
+    fn test_duration() {
+        assert_eq!(duration("500ms", TimeUnit::Millis), Ok(500));
+        assert_eq!(duration("2s", TimeUnit::Millis), Ok(2000));
+        assert_eq!(duration("1h", TimeUnit::Minutes), Ok(60));
+        assert_eq!(duration("0.5m", TimeUnit::Seconds), Ok(30));
+        assert_eq!(
+            duration("1.0005s", TimeUnit::Millis),
+            Err(Invalid::Precision)
+        );
+        assert_eq!(duration("-5m", TimeUnit::Minutes), Err(Invalid::TooLow));
+        assert_eq!(duration("m", TimeUnit::Minutes), Err(Invalid::Syntax));
+        assert_eq!(duration("5 m", TimeUnit::Minutes), Err(Invalid::Unit));
+        assert_eq!(duration("2000h", TimeUnit::Millis), Err(Invalid::TooHigh));
+    }
+
+    #[test]
+    fn test_frequency() {
+        assert_eq!(frequency("440Hz"), Ok(440));
+        assert_eq!(frequency("1.25M"), Ok(1_250_000));
+        assert_eq!(frequency("1kHZ"), Err(Invalid::Unit));
+        assert_eq!(frequency("5G"), Err(Invalid::Unit));
+        assert_eq!(frequency("-1k"), Err(Invalid::TooLow));
+        assert_eq!(frequency("5000M"), Err(Invalid::TooHigh));
+    }
+
+    #[test]
+    fn test_percent() {
+        assert_eq!(percent("0%"), Ok(0));
+        assert_eq!(percent("100%"), Ok(1000));
+        assert_eq!(percent("12.25%"), Err(Invalid::Precision));
+        assert_eq!(percent("-5%"), Err(Invalid::TooLow));
+        assert_eq!(percent("%"), Err(Invalid::Syntax));
+    }
+
+    #[test]
+    fn test_in_range() {
+        assert_eq!(in_range(5, 1, 255), Ok(5));
+        assert_eq!(in_range(0, 1, 255), Err(Invalid::TooLow));
+        assert_eq!(in_range(256, 1, 255), Err(Invalid::TooHigh));
+    }
+
+    #[test]
+    fn test_write() {
+        let fixed = |value, decimals| {
+            let mut out = std::string::String::new();
+            write_fixed(&mut out, value, decimals).unwrap();
+            out
+        };
+        assert_eq!(fixed(-4000, 2), "-40");
+        assert_eq!(fixed(12550, 2), "125.5");
+        assert_eq!(fixed(-5, 2), "-0.05");
+        assert_eq!(fixed(7, 0), "7");
+        let hz = |hz| {
+            let mut out = std::string::String::new();
+            write_hz(&mut out, hz).unwrap();
+            out
+        };
+        assert_eq!(hz(20), "20Hz");
+        assert_eq!(hz(20_000), "20kHz");
+        assert_eq!(hz(2500), "2500Hz");
+    }
+}
@@ -0,0 +1,258 @@
+//! Error diagnostics
+//!
+//! A `Diagnostic` tells where and why `parse_diagnostic` failed: the byte
+//! span of the offending token in the line, the command and what it
+//! expected. Its `Display` repeats the line and underlines the token, e.g.,
+//!
+//! ``` text
+//! duty 800
+//!      ^^^ too high, duty expects 0..=255 or 0%..=100%
+//! ```
+//!
+//! The lines end in `\r\n`, so the message can be sent over the serial port
+//! as is.
+
+use core::fmt;
+use core::ops::Range;
+use core::str::SplitWhitespace;
+
+use heapless::String;
+
+use crate::args::Invalid;
+use crate::Error;
+
+/// Longest expected argument text, longer texts are left out
+pub const EXPECTED_LEN: usize = 48;
+
+#[derive(Debug, PartialEq)]
+pub struct Diagnostic<'a> {
+    /// The parsed line, up to the first invalid byte for `Error::NonUtf8`
+    pub line: &'a str,
+    pub error: Error,
+    /// Why the argument is invalid, e.g., `Invalid::TooHigh` for
+    /// `Error::ArgError`, `None` for other errors
+    pub reason: Option<Invalid>,
+    /// Byte span of the offending token in `line`, empty at the end of the
+    /// line for a missing argument
+    pub span: Range<usize>,
+    /// The command and subcommand, e.g., "alarm add", empty if not found
+    pub command: &'a str,
+    /// What the argument should be, e.g., "0..=255", derived from the
+    /// command table
+    pub expected: Option<String<EXPECTED_LEN>>,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Error::NonUtf8 => "not UTF-8",
+            Error::CommandNotFound => "unknown command",
+            Error::ArgMissing => "missing argument",
+            Error::ArgError => "invalid argument",
+            Error::ArgNumber => "too many arguments",
+            Error::InvalidTime => "invalid time",
+            Error::InvalidDate => "invalid date",
+            Error::InvalidMelody => "invalid melody",
+        })
+    }
+}
+
+impl fmt::Display for Diagnostic<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // columns in characters, the line may hold multi-byte characters
+        let column = self.line[..self.span.start].chars().count();
+        let width = self.line[self.span.clone()].chars().count().max(1);
+        write!(f, "{}\r\n", self.line)?;
+        for _ in 0..column {
+            f.write_str(" ")?;
+        }
+        for _ in 0..width {
+            f.write_str("^")?;
+        }
+        match self.reason {
+            Some(reason) => write!(f, " {}", reason)?,
+            None => write!(f, " {}", self.error)?,
+        }
+        match &self.expected {
+            Some(expected) if self.error != Error::ArgNumber => {
+                write!(f, ", {} expects {}", self.command, expected)?
+            }
+            _ => {}
+        }
+        f.write_str("\r\n")
+    }
+}
+
+/// The whitespace separated tokens of a line with their spans, keeping
+/// track of the command for a `Diagnostic`
+#[derive(Clone)]
+pub(crate) struct Tokens<'a> {
+    line: &'a str,
+    split: SplitWhitespace<'a>,
+    /// Span of the last token
+    span: Range<usize>,
+    command: Range<usize>,
+    reason: Option<Invalid>,
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let token = self.split.next()?;
+        self.span = self.offset(token)..self.offset(token) + token.len();
+        Some(token)
+    }
+}
+
+impl<'a> Tokens<'a> {
+    pub fn new(line: &'a str) -> Self {
+        Self {
+            line,
+            split: line.split_whitespace(),
+            span: 0..0,
+            command: 0..0,
+            reason: None,
+        }
+    }
+
+    pub fn peek(&self) -> Option<&'a str> {
+        self.split.clone().next()
+    }
+
+    /// The command, the first token
+    pub fn command(&mut self) -> Option<&'a str> {
+        let token = self.next()?;
+        self.command = self.span.clone();
+        Some(token)
+    }
+
+    /// Add the last token, a subcommand, to the command
+    pub fn subcommand(&mut self) {
+        self.command.end = self.span.end;
+    }
+
+    /// The command and subcommands so far
+    pub fn command_str(&self) -> &'a str {
+        &self.line[self.command.clone()]
+    }
+
+    /// The rest of the line, trimmed, as the last argument, `None` if
+    /// empty
+    pub fn rest(&mut self) -> Option<&'a str> {
+        let rest = self.line[self.span.end..].trim();
+        for _ in self.split.by_ref() {}
+        if rest.is_empty() {
+            return None;
+        }
+        self.span = self.offset(rest)..self.offset(rest) + rest.len();
+        Some(rest)
+    }
+
+    /// The last token is an invalid argument
+    pub fn invalid(&mut self, reason: Invalid) {
+        self.reason = Some(reason);
+    }
+
+    pub fn diagnostic(
+        &self,
+        error: Error,
+        expected: Option<String<EXPECTED_LEN>>,
+    ) -> Diagnostic<'a> {
+        let end = self.line.len();
+        Diagnostic {
+            line: self.line,
+            span: match error {
+                Error::ArgMissing | Error::NonUtf8 => end..end,
+                _ => self.span.clone(),
+            },
+            command: match error {
+                Error::CommandNotFound => "",
+                _ => &self.line[self.command.clone()],
+            },
+            expected,
+            reason: self.reason,
+            error,
+        }
+    }
+
+    fn offset(&self, token: &str) -> usize {
+        token.as_ptr() as usize - self.line.as_ptr() as usize
+    }
+}
+
+#[cfg(test)]
+mod test_diagnostic {
+    use crate::*;
+
+    fn message(line: &str) -> std::string::String {
+        parse_diagnostic(line.as_bytes()).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_spans() {
+        let diagnostic = parse_diagnostic(b"duty 800").unwrap_err();
+        assert_eq!(diagnostic.error, Error::ArgError);
+        assert_eq!(diagnostic.reason, Some(args::Invalid::TooHigh));
+        assert_eq!(diagnostic.span, 5..8);
+        assert_eq!(diagnostic.command, "duty");
+        assert_eq!(diagnostic.expected.as_deref(), Some("0..=255 or 0%..=100%"));
+
+        let diagnostic = parse_diagnostic(b"  alarm  add 7:30  daily work overtime").unwrap_err();
+        assert_eq!(diagnostic.error, Error::ArgNumber);
+        assert_eq!(diagnostic.span, 30..38);
+        assert_eq!(diagnostic.command, "alarm  add");
+
+        let diagnostic = parse_diagnostic(b"bogus 1").unwrap_err();
+        assert_eq!(
+            (diagnostic.error, diagnostic.span),
+            (Error::CommandNotFound, 0..5)
+        );
+        assert_eq!(diagnostic.command, "");
+    }
+
+    #[test]
+    fn test_messages() {
+        assert_eq!(
+            message("duty 800"),
+            "duty 800\r\n     ^^^ too high, duty expects 0..=255 or 0%..=100%\r\n"
+        );
+        assert_eq!(
+            message("snooze 90s"),
+            "snooze 90s\r\n       ^^^ too precise, snooze expects 1..=255 minutes\r\n"
+        );
+        assert_eq!(
+            message("freq 2.5GHz"),
+            "freq 2.5GHz\r\n     ^^^^^^ unknown unit, freq expects a frequency like 440 or 2.5kHz\r\n"
+        );
+        assert_eq!(
+            message("time 24:00:00"),
+            "time 24:00:00\r\n     ^^^^^^^^ invalid time, time expects hh:mm:ss\r\n"
+        );
+        assert_eq!(
+            message("alarm add"),
+            "alarm add\r\n         ^ missing argument, alarm add expects hh:mm\r\n"
+        );
+        assert_eq!(
+            message("stop now"),
+            "stop now\r\n     ^^^ too many arguments\r\n"
+        );
+        assert_eq!(
+            message("cal add °F"),
+            "cal add °F\r\n        ^^ invalid argument, cal add expects -40..=125 C\r\n"
+        );
+        assert_eq!(
+            message("cal add -40.5"),
+            "cal add -40.5\r\n        ^^^^^ too low, cal add expects -40..=125 C\r\n"
+        );
+        assert_eq!(message("halt"), "halt\r\n^^^^ unknown command\r\n");
+    }
+
+    #[test]
+    fn test_non_utf8() {
+        let diagnostic = parse_diagnostic(b"play \xffoo").unwrap_err();
+        assert_eq!(diagnostic.error, Error::NonUtf8);
+        assert_eq!(diagnostic.line, "play ");
+        assert_eq!(diagnostic.to_string(), "play \r\n     ^ not UTF-8\r\n");
+    }
+}
@@ -5,16 +5,98 @@
 // no_std library except for test
 #![cfg_attr(not(test), no_std)]
 
+pub mod args;
+pub mod diagnostic;
+pub mod rtttl;
+pub mod table;
+
+use args::TimeUnit;
+pub use diagnostic::Diagnostic;
+use table::{Arg, Kind, Registry, Spec};
+
+use heapless::String;
+
+/// Maximum length of an alarm label
+pub const LABEL_LEN: usize = 16;
+
+/// Maximum length of the command given with `help`, longer than any
+/// command name
+pub const HELP_LEN: usize = 32;
+
+/// Maximum length of an RTTTL melody given with `melody`
+pub const MELODY_LEN: usize = 192;
+
+/// Weekday mask for alarms, bit 0 is Monday and bit 6 is Sunday
+pub const DAILY: u8 = 0x7f;
+pub const WEEKDAYS: u8 = 0x1f;
+pub const WEEKEND: u8 = 0x60;
+
+/// Short weekday names, Monday first
+pub const DAY_NAMES: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+/// Temperature unit selected with `units c|f|k`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Unit {
+    Celsius,
+    Fahrenheit,
+    Kelvin,
+}
+
 #[derive(Debug, PartialEq)]
 pub enum Command {
     FrequencyHz(u32),
     Duty(u8),
     Start,
     Stop,
+    SetTime {
+        h: u8,
+        m: u8,
+        s: u8,
+    },
+    SetDate {
+        y: u16,
+        mo: u8,
+        d: u8,
+    },
+    GetTime,
+    AlarmAdd {
+        h: u8,
+        m: u8,
+        days: u8,
+        label: String<LABEL_LEN>,
+    },
+    AlarmList,
+    AlarmDel(u8),
+    AlarmEnable(u8),
+    AlarmDisable(u8),
+    SnoozeMinutes(u8),
+    Play(String<LABEL_LEN>),
+    Melody(String<MELODY_LEN>),
+    /// Offset from UTC in minutes, -720..=840
+    TimeZone(i16),
+    Save,
+    Load,
+    FactoryReset,
+    /// Dump the temperature history as CSV
+    TempHistory,
+    /// Add a thermistor calibration point at the given °C, -40..=125
+    CalAdd(f32),
+    /// Fit the thermistor model to the calibration points
+    CalFit,
+    CalShow,
+    /// Drop the calibration points and restore the default model
+    CalReset,
+    /// Report the thermistor, on-die temperature and supply voltage
+    Sensors,
+    /// Unit of the display, serial replies and logs
+    Units(Unit),
+    /// List the commands, or describe those starting with the words, see
+    /// `Registry::write_help`, the words separated by single spaces
+    Help(String<HELP_LEN>),
 }
 
 /// Parse a byte slice to an `Option<Command>`
-/// None indicates an error
+/// None indicates an error, text after the last argument is ignored
 ///
 /// # Examples
 /// ```
@@ -39,25 +121,7 @@ pub enum Command {
 /// );
 /// ```
 pub fn parse(bytes: &[u8]) -> Option<Command> {
-    let mut split = bytes.splitn(bytes.len(), |c| *c == b' ');
-    let next = split.next()?;
-    match next {
-        b"start" => Some(Command::Start),
-        b"stop" => Some(Command::Stop),
-        b"freq" => {
-            let next = split.next()?;
-            let str = core::str::from_utf8(next).ok()?;
-            let v: u32 = str.parse().ok()?;
-            Some(Command::FrequencyHz(v))
-        }
-        b"duty" => {
-            let next = split.next()?;
-            let str = core::str::from_utf8(next).ok()?;
-            let val: u8 = str.parse().ok()?;
-            Some(Command::Duty(val))
-        }
-        _ => None,
-    }
+    COMMANDS.parse_prefix(bytes).ok()
 }
 
 #[cfg(test)]
@@ -123,8 +187,241 @@ pub enum Error {
     ArgError,
     /// Nr Arguments wrong
     ArgNumber,
+    /// Hour, minute or second out of range
+    InvalidTime,
+    /// Year, month or day out of range
+    InvalidDate,
+    /// RTTTL melody could not be parsed
+    InvalidMelody,
+}
+
+/// Number of days in month `mo` (1..=12) of year `y`, leap years included
+///
+/// # Examples
+/// ```
+/// use command_parser::*;
+/// assert_eq!(days_in_month(2024, 2), 29);
+/// assert_eq!(days_in_month(2100, 2), 28);
+/// ```
+pub fn days_in_month(y: u16, mo: u8) -> u8 {
+    match mo {
+        2 if (y.is_multiple_of(4) && !y.is_multiple_of(100)) || y.is_multiple_of(400) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// A heapless string of a text argument, which is at most `N` bytes
+fn string<const N: usize>(text: &str) -> Result<String<N>, Error> {
+    text.parse().map_err(|_| Error::ArgError)
 }
 
+/// Tone generator commands
+pub const BUZZER: &[Spec<Command>] = &[
+    Spec::new("freq", "set the tone frequency", |v| {
+        Ok(Command::FrequencyHz(v.uint(0)?))
+    })
+    .args(&[Arg::new(
+        "hz",
+        Kind::Frequency {
+            min: 0,
+            max: u32::MAX,
+        },
+    )
+    .help("the pitch")]),
+    Spec::new("duty", "set the volume, the duty cycle of the tone", |v| {
+        Ok(Command::Duty(v.uint(0)? as u8))
+    })
+    .args(&[Arg::new("duty", Kind::Level { max: 255 }).help("0 is silent")]),
+    Spec::new("start", "start the tone", |_| Ok(Command::Start)),
+    Spec::new("stop", "stop the tone", |_| Ok(Command::Stop)),
+];
+
+/// Real time clock commands
+pub const CLOCK: &[Spec<Command>] = &[
+    Spec::new("time", "show the time, or set it", |v| {
+        Ok(match v.time(0) {
+            Ok((h, m, s)) => Command::SetTime { h, m, s },
+            Err(_) => Command::GetTime,
+        })
+    })
+    .args(&[Arg::new("time", Kind::Time)
+        .optional()
+        .help("shows the time if left out")]),
+    Spec::new("date", "set the date", |v| {
+        let (y, mo, d) = v.date(0)?;
+        Ok(Command::SetDate { y, mo, d })
+    })
+    .args(&[Arg::new("date", Kind::Date)]),
+    Spec::new("tz", "set the offset from UTC", |v| {
+        Ok(Command::TimeZone(v.int(0)? as i16))
+    })
+    .args(&[Arg::new(
+        "offset",
+        Kind::Int {
+            min: -720,
+            max: 840,
+        },
+    )
+    .unit("minutes")
+    .help("e.g., 60 for UTC+1")]),
+];
+
+/// Alarm commands
+pub const ALARMS: &[Spec<Command>] = &[
+    Spec::new(
+        "alarm add",
+        "add an alarm, daily unless days are given",
+        |v| {
+            let (h, m, _) = v.time(0)?;
+            Ok(Command::AlarmAdd {
+                h,
+                m,
+                days: v.days(1).unwrap_or(DAILY),
+                label: string(v.text(2).unwrap_or(""))?,
+            })
+        },
+    )
+    .args(&[
+        Arg::new("time", Kind::TimeOfDay),
+        Arg::new("days", Kind::Days)
+            .optional()
+            .help("daily if left out"),
+        Arg::new("label", Kind::Text { len: LABEL_LEN })
+            .optional()
+            .help("shown when the alarm rings"),
+    ]),
+    Spec::new("alarm list", "list the alarms", |_| Ok(Command::AlarmList)).aliases(&["alarm ls"]),
+    Spec::new("alarm del", "delete an alarm", |v| {
+        Ok(Command::AlarmDel(v.uint(0)? as u8))
+    })
+    .aliases(&["alarm rm"])
+    .args(&[Arg::new("index", Kind::Uint { min: 0, max: 255 }).help("from alarm list")]),
+    Spec::new("alarm enable", "enable an alarm", |v| {
+        Ok(Command::AlarmEnable(v.uint(0)? as u8))
+    })
+    .args(&[Arg::new("index", Kind::Uint { min: 0, max: 255 }).help("from alarm list")]),
+    Spec::new("alarm disable", "disable an alarm", |v| {
+        Ok(Command::AlarmDisable(v.uint(0)? as u8))
+    })
+    .args(&[Arg::new("index", Kind::Uint { min: 0, max: 255 }).help("from alarm list")]),
+    Spec::new("snooze", "snooze the ringing alarm", |v| {
+        Ok(Command::SnoozeMinutes(v.uint(0)? as u8))
+    })
+    .args(&[Arg::new(
+        "minutes",
+        Kind::Duration {
+            min: 1,
+            max: 255,
+            unit: TimeUnit::Minutes,
+        },
+    )
+    .help("or a duration like 1h")]),
+];
+
+/// Melody commands
+pub const MELODIES: &[Spec<Command>] = &[
+    Spec::new("play", "play a built-in melody", |v| {
+        Ok(Command::Play(string(v.text(0)?)?))
+    })
+    .args(&[Arg::new("name", Kind::Text { len: LABEL_LEN }).help("a melody of the firmware")]),
+    Spec::new("melody", "play an RTTTL melody", |v| {
+        // RTTTL may contain spaces, the melody is the rest of the line
+        let melody = v.text(0)?;
+        rtttl::parse(melody).map_err(|_| Error::InvalidMelody)?;
+        Ok(Command::Melody(string(melody)?))
+    })
+    .args(&[
+        Arg::new("rtttl", Kind::Rest { len: MELODY_LEN }).help("e.g., beep:d=4,o=5,b=120:c,e,g")
+    ]),
+];
+
+/// Temperature sensor commands
+pub const SENSORS: &[Spec<Command>] = &[
+    Spec::new(
+        "temp history",
+        "dump the temperature history as CSV",
+        |_| Ok(Command::TempHistory),
+    ),
+    Spec::new(
+        "cal add",
+        "add a calibration point at the given temperature",
+        |v| Ok(Command::CalAdd(v.fixed(0)? as f32 / 100.0)),
+    )
+    .args(&[Arg::new(
+        "celsius",
+        Kind::Fixed {
+            min: -4000,
+            max: 12500,
+            decimals: 2,
+        },
+    )
+    .unit("C")
+    .help("the temperature of the thermistor now")]),
+    Spec::new("cal fit", "fit the model to the calibration points", |_| {
+        Ok(Command::CalFit)
+    }),
+    Spec::new("cal show", "show the model and calibration points", |_| {
+        Ok(Command::CalShow)
+    }),
+    Spec::new("cal reset", "restore the default model", |_| {
+        Ok(Command::CalReset)
+    }),
+    Spec::new("sensors", "read all temperature sensors", |_| {
+        Ok(Command::Sensors)
+    }),
+    Spec::new("units", "set the temperature unit", |v| {
+        const UNITS: [Unit; 3] = [Unit::Celsius, Unit::Fahrenheit, Unit::Kelvin];
+        Ok(Command::Units(UNITS[v.keyword(0)?]))
+    })
+    .args(&[
+        Arg::new("unit", Kind::Keyword(&["c", "f", "k"])).help("Celsius, Fahrenheit or Kelvin")
+    ]),
+];
+
+/// Settings storage commands
+pub const SETTINGS: &[Spec<Command>] = &[
+    Spec::new("save", "save the settings to flash", |_| Ok(Command::Save)),
+    Spec::new("load", "load the settings from flash", |_| {
+        Ok(Command::Load)
+    }),
+    Spec::new("factory-reset", "restore the default settings", |_| {
+        Ok(Command::FactoryReset)
+    }),
+];
+
+/// Help on the commands
+pub const HELP: &[Spec<Command>] =
+    &[
+        Spec::new("help", "list the commands, or describe one", |v| {
+            let command = v.text(0).unwrap_or_default();
+            match COMMANDS.matching(command).next() {
+                Some(_) => {
+                    let mut words = String::new();
+                    for word in command.split_whitespace() {
+                        if !words.is_empty() {
+                            words.push(' ').map_err(|_| Error::ArgError)?;
+                        }
+                        words.push_str(word).map_err(|_| Error::ArgError)?;
+                    }
+                    Ok(Command::Help(words))
+                }
+                None => Err(Error::ArgError),
+            }
+        })
+        .args(&[Arg::new("command", Kind::Command)
+            .optional()
+            .help("e.g., alarm add")]),
+    ];
+
+/// The tables of the built-in commands
+pub const TABLES: &[&[Spec<Command>]] = &[BUZZER, CLOCK, ALARMS, MELODIES, SENSORS, SETTINGS, HELP];
+
+/// The built-in commands, used by `parse`, `parse_result` and
+/// `parse_diagnostic`
+pub const COMMANDS: Registry<'static, Command> = Registry::new(TABLES);
+
 /// Parse a byte slice to an `Result<Command, Error>`
 /// The Error type is locally defined indicating the error
 ///
@@ -150,30 +447,40 @@ pub enum Error {
 ///     Ok(Command::Duty(8))
 /// );
 /// ```
+/// ```
+/// use command_parser::*;
+/// assert_eq!(
+///     parse_result(b"time 07:30:00"),
+///     Ok(Command::SetTime { h: 7, m: 30, s: 0 })
+/// );
+/// ```
+/// ```
+/// use command_parser::*;
+/// assert_eq!(
+///     parse_result(b"date 2026-10-18"),
+///     Ok(Command::SetDate { y: 2026, mo: 10, d: 18 })
+/// );
+/// ```
 pub fn parse_result(bytes: &[u8]) -> Result<Command, Error> {
-    // let's work on &str instead of raw byte arrays
-    let str = core::str::from_utf8(bytes).map_err(|_| Error::NonUtf8)?;
-    let mut split = str.split_whitespace();
-    let next = split.next().ok_or(Error::CommandNotFound)?;
-    let result = match next {
-        "start" => Ok(Command::Start),
-        "stop" => Ok(Command::Stop),
-        "freq" => {
-            let next = split.next().ok_or(Error::ArgMissing)?;
-            let v: u32 = next.parse().map_err(|_| Error::ArgError)?;
-            Ok(Command::FrequencyHz(v))
-        }
-        "duty" => {
-            let next = split.next().ok_or(Error::ArgMissing)?;
-            let val: u8 = next.parse().map_err(|_| Error::ArgError)?;
-            Ok(Command::Duty(val))
-        }
-        _ => Err(Error::CommandNotFound)?,
-    };
-    match split.next() {
-        None => result,
-        _ => Err(Error::ArgNumber),
-    }
+    parse_diagnostic(bytes).map_err(|diagnostic| diagnostic.error)
+}
+
+/// Parse a byte slice like `parse_result`, but on failure tell where and
+/// why, see `Diagnostic`
+///
+/// # Examples
+/// ```
+/// use command_parser::*;
+/// let diagnostic = parse_diagnostic(b"duty 800").unwrap_err();
+/// assert_eq!(diagnostic.error, Error::ArgError);
+/// assert_eq!(diagnostic.span, 5..8);
+/// assert_eq!(
+///     diagnostic.to_string(),
+///     "duty 800\r\n     ^^^ too high, duty expects 0..=255 or 0%..=100%\r\n"
+/// );
+/// ```
+pub fn parse_diagnostic(bytes: &[u8]) -> Result<Command, Diagnostic<'_>> {
+    COMMANDS.parse(bytes)
 }
 
 #[cfg(test)]
@@ -250,4 +557,269 @@ mod test_parse_result {
             Err(Error::ArgError)
         );
     }
+
+    #[test]
+    fn test_parse_result_get_time() {
+        assert_eq!(parse_result(b"time"), Ok(Command::GetTime));
+    }
+
+    #[test]
+    fn test_parse_result_set_time() {
+        assert_eq!(
+            parse_result(b"time 23:59:59"),
+            Ok(Command::SetTime {
+                h: 23,
+                m: 59,
+                s: 59
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_result_set_time_range() {
+        assert_eq!(parse_result(b"time 24:00:00"), Err(Error::InvalidTime));
+        assert_eq!(parse_result(b"time 12:60:00"), Err(Error::InvalidTime));
+        assert_eq!(parse_result(b"time 12:00:60"), Err(Error::InvalidTime));
+    }
+
+    #[test]
+    fn test_parse_result_set_time_format() {
+        assert_eq!(parse_result(b"time 12:00"), Err(Error::ArgError));
+        assert_eq!(parse_result(b"time 12:00:00:00"), Err(Error::ArgError));
+        assert_eq!(parse_result(b"time 12-00-00"), Err(Error::ArgError));
+        assert_eq!(parse_result(b"time 12:00:00 x"), Err(Error::ArgNumber));
+    }
+
+    #[test]
+    fn test_parse_result_set_date() {
+        assert_eq!(
+            parse_result(b"date 2026-10-18"),
+            Ok(Command::SetDate {
+                y: 2026,
+                mo: 10,
+                d: 18
+            })
+        );
+        assert_eq!(
+            parse_result(b"date 2024-02-29"),
+            Ok(Command::SetDate {
+                y: 2024,
+                mo: 2,
+                d: 29
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_result_set_date_range() {
+        assert_eq!(parse_result(b"date 2026-13-01"), Err(Error::InvalidDate));
+        assert_eq!(parse_result(b"date 2026-00-01"), Err(Error::InvalidDate));
+        assert_eq!(parse_result(b"date 2026-04-31"), Err(Error::InvalidDate));
+        assert_eq!(parse_result(b"date 2026-02-29"), Err(Error::InvalidDate));
+        assert_eq!(parse_result(b"date 2100-02-29"), Err(Error::InvalidDate));
+        assert_eq!(parse_result(b"date 2026-10-00"), Err(Error::InvalidDate));
+        assert_eq!(parse_result(b"date 1969-12-31"), Err(Error::InvalidDate));
+        assert_eq!(parse_result(b"date 2200-01-01"), Err(Error::InvalidDate));
+        assert_eq!(parse_result(b"date 2106-02-08"), Err(Error::InvalidDate));
+        assert_eq!(parse_result(b"date 2026-300-01"), Err(Error::InvalidDate));
+        assert_eq!(
+            parse_result(b"date 2106-02-07"),
+            Ok(Command::SetDate {
+                y: 2106,
+                mo: 2,
+                d: 7
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_result_set_date_missing() {
+        assert_eq!(parse_result(b"date"), Err(Error::ArgMissing));
+        assert_eq!(parse_result(b"date 2026/10/18"), Err(Error::ArgError));
+    }
+
+    #[test]
+    fn test_parse_result_alarm_add() {
+        assert_eq!(
+            parse_result(b"alarm add 07:30"),
+            Ok(Command::AlarmAdd {
+                h: 7,
+                m: 30,
+                days: DAILY,
+                label: String::new()
+            })
+        );
+        assert_eq!(
+            parse_result(b"alarm add 07:30 weekdays work"),
+            Ok(Command::AlarmAdd {
+                h: 7,
+                m: 30,
+                days: WEEKDAYS,
+                label: "work".parse().unwrap()
+            })
+        );
+        assert_eq!(
+            parse_result(b"alarm add 09:00 mon,sun"),
+            Ok(Command::AlarmAdd {
+                h: 9,
+                m: 0,
+                days: 0b100_0001,
+                label: String::new()
+            })
+        );
+        assert_eq!(
+            parse_result(b"alarm add 09:00 gym"),
+            Ok(Command::AlarmAdd {
+                h: 9,
+                m: 0,
+                days: DAILY,
+                label: "gym".parse().unwrap()
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_result_alarm_add_error() {
+        assert_eq!(parse_result(b"alarm"), Err(Error::ArgMissing));
+        assert_eq!(parse_result(b"alarm add"), Err(Error::ArgMissing));
+        assert_eq!(parse_result(b"alarm add 24:00"), Err(Error::InvalidTime));
+        assert_eq!(parse_result(b"alarm add 07:30:00"), Err(Error::ArgError));
+        assert_eq!(
+            parse_result(b"alarm add 07:30 daily a_label_that_is_too_long"),
+            Err(Error::ArgError)
+        );
+        assert_eq!(
+            parse_result(b"alarm add 07:30 daily label extra"),
+            Err(Error::ArgNumber)
+        );
+    }
+
+    #[test]
+    fn test_parse_result_alarm_index() {
+        assert_eq!(parse_result(b"alarm list"), Ok(Command::AlarmList));
+        assert_eq!(parse_result(b"alarm del 3"), Ok(Command::AlarmDel(3)));
+        assert_eq!(parse_result(b"alarm enable 0"), Ok(Command::AlarmEnable(0)));
+        assert_eq!(
+            parse_result(b"alarm disable 7"),
+            Ok(Command::AlarmDisable(7))
+        );
+        assert_eq!(parse_result(b"alarm del"), Err(Error::ArgMissing));
+        assert_eq!(parse_result(b"alarm del x"), Err(Error::ArgError));
+        assert_eq!(parse_result(b"alarm snooze"), Err(Error::ArgError));
+    }
+
+    #[test]
+    fn test_parse_result_snooze() {
+        assert_eq!(parse_result(b"snooze 10"), Ok(Command::SnoozeMinutes(10)));
+        assert_eq!(parse_result(b"snooze"), Err(Error::ArgMissing));
+        assert_eq!(parse_result(b"snooze 0"), Err(Error::ArgError));
+        assert_eq!(parse_result(b"snooze 300"), Err(Error::ArgError));
+        assert_eq!(parse_result(b"snooze 5m"), Ok(Command::SnoozeMinutes(5)));
+        assert_eq!(parse_result(b"snooze 600s"), Ok(Command::SnoozeMinutes(10)));
+        assert_eq!(parse_result(b"snooze 2h"), Ok(Command::SnoozeMinutes(120)));
+        assert_eq!(parse_result(b"snooze 90s"), Err(Error::ArgError));
+        assert_eq!(parse_result(b"snooze 5h"), Err(Error::ArgError));
+    }
+
+    #[test]
+    fn test_parse_result_rich_args() {
+        assert_eq!(parse_result(b"duty 50%"), Ok(Command::Duty(128)));
+        assert_eq!(parse_result(b"duty 100%"), Ok(Command::Duty(255)));
+        assert_eq!(parse_result(b"duty 0x40"), Ok(Command::Duty(64)));
+        assert_eq!(parse_result(b"duty 101%"), Err(Error::ArgError));
+        assert_eq!(parse_result(b"freq 2.5kHz"), Ok(Command::FrequencyHz(2500)));
+        assert_eq!(parse_result(b"freq 1k"), Ok(Command::FrequencyHz(1000)));
+        assert_eq!(parse_result(b"freq 0x1b8"), Err(Error::ArgError));
+        assert_eq!(parse_result(b"tz -0x3c"), Ok(Command::TimeZone(-60)));
+        assert_eq!(parse_result(b"cal add 25.5"), Ok(Command::CalAdd(25.5)));
+        assert_eq!(parse_result(b"cal add 25.125"), Err(Error::ArgError));
+        assert_eq!(
+            parse_result(b"cal add -92233720368547758.08"),
+            Err(Error::ArgError)
+        );
+    }
+
+    #[test]
+    fn test_parse_result_play() {
+        assert_eq!(
+            parse_result(b"play nokia"),
+            Ok(Command::Play("nokia".parse().unwrap()))
+        );
+        assert_eq!(parse_result(b"play"), Err(Error::ArgMissing));
+        assert_eq!(parse_result(b"play nokia tetris"), Err(Error::ArgNumber));
+    }
+
+    #[test]
+    fn test_parse_result_melody() {
+        assert_eq!(
+            parse_result(b"melody  beep:d=4, o=5, b=120: c, e, g  "),
+            Ok(Command::Melody(
+                "beep:d=4, o=5, b=120: c, e, g".parse().unwrap()
+            ))
+        );
+        assert_eq!(parse_result(b"melody   "), Err(Error::ArgMissing));
+        assert_eq!(
+            parse_result(b"melody beep:d=4:x"),
+            Err(Error::InvalidMelody)
+        );
+    }
+
+    #[test]
+    fn test_parse_result_tz() {
+        assert_eq!(parse_result(b"tz 60"), Ok(Command::TimeZone(60)));
+        assert_eq!(parse_result(b"tz +60"), Ok(Command::TimeZone(60)));
+        assert_eq!(parse_result(b"tz -330"), Ok(Command::TimeZone(-330)));
+        assert_eq!(parse_result(b"tz 840"), Ok(Command::TimeZone(840)));
+        assert_eq!(parse_result(b"tz -721"), Err(Error::ArgError));
+        assert_eq!(parse_result(b"tz"), Err(Error::ArgMissing));
+    }
+
+    #[test]
+    fn test_parse_result_settings() {
+        assert_eq!(parse_result(b"save"), Ok(Command::Save));
+        assert_eq!(parse_result(b"load"), Ok(Command::Load));
+        assert_eq!(parse_result(b"factory-reset"), Ok(Command::FactoryReset));
+        assert_eq!(parse_result(b"save now"), Err(Error::ArgNumber));
+    }
+
+    #[test]
+    fn test_parse_result_temp_history() {
+        assert_eq!(parse_result(b"temp history"), Ok(Command::TempHistory));
+        assert_eq!(parse_result(b"temp"), Err(Error::ArgMissing));
+        assert_eq!(parse_result(b"temp now"), Err(Error::ArgError));
+        assert_eq!(parse_result(b"temp history 5"), Err(Error::ArgNumber));
+    }
+
+    #[test]
+    fn test_parse_result_sensors() {
+        assert_eq!(parse_result(b"sensors"), Ok(Command::Sensors));
+        assert_eq!(parse_result(b"sensors all"), Err(Error::ArgNumber));
+    }
+
+    #[test]
+    fn test_parse_result_units() {
+        assert_eq!(parse_result(b"units c"), Ok(Command::Units(Unit::Celsius)));
+        assert_eq!(
+            parse_result(b"units f"),
+            Ok(Command::Units(Unit::Fahrenheit))
+        );
+        assert_eq!(parse_result(b"units k"), Ok(Command::Units(Unit::Kelvin)));
+        assert_eq!(parse_result(b"units x"), Err(Error::ArgError));
+        assert_eq!(parse_result(b"units"), Err(Error::ArgMissing));
+        assert_eq!(parse_result(b"units c f"), Err(Error::ArgNumber));
+    }
+
+    #[test]
+    fn test_parse_result_cal() {
+        assert_eq!(parse_result(b"cal add 0"), Ok(Command::CalAdd(0.0)));
+        assert_eq!(parse_result(b"cal add -12.5"), Ok(Command::CalAdd(-12.5)));
+        assert_eq!(parse_result(b"cal add 125.1"), Err(Error::ArgError));
+        assert_eq!(parse_result(b"cal add NaN"), Err(Error::ArgError));
+        assert_eq!(parse_result(b"cal add"), Err(Error::ArgMissing));
+        assert_eq!(parse_result(b"cal fit"), Ok(Command::CalFit));
+        assert_eq!(parse_result(b"cal show"), Ok(Command::CalShow));
+        assert_eq!(parse_result(b"cal reset"), Ok(Command::CalReset));
+        assert_eq!(parse_result(b"cal"), Err(Error::ArgMissing));
+        assert_eq!(parse_result(b"cal fit 3"), Err(Error::ArgNumber));
+    }
 }
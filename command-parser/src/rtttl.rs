@@ -0,0 +1,273 @@
+//! RTTTL (Ring Tone Text Transfer Language) parser
+//!
+//! A melody is given as `name:defaults:notes`, e.g.,
+//! `beep:d=4,o=5,b=120:c,e,g,2c6`. The defaults section sets the duration
+//! (`d`), octave (`o`) and tempo in beats per minute (`b`) used by notes
+//! that do not give their own.
+//!
+//! Each note is `[duration]letter[#][.][octave][.]` where the letter is
+//! `a`..`g`, or `p` for a pause. A dot makes the note 50% longer.
+
+/// Error type for `parse`
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    /// Name, defaults or notes section missing
+    MissingSection,
+    /// Illegal entry in the defaults section
+    InvalidDefault,
+    /// Illegal note, `usize` is the index of the note
+    InvalidNote(usize),
+}
+
+/// A single tone (or pause)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Note {
+    /// Frequency in Hz, 0 for a pause
+    pub frequency_hz: u32,
+    pub duration_ms: u32,
+}
+
+/// Frequencies in octave 4 (c to b) in centi Hz
+const OCTAVE_4: [u32; 12] = [
+    26163, 27718, 29366, 31113, 32963, 34923, 36999, 39200, 41530, 44000, 46616, 49388,
+];
+
+const DURATIONS: [u32; 6] = [1, 2, 4, 8, 16, 32];
+const OCTAVES: core::ops::RangeInclusive<u32> = 3..=8;
+
+/// A parsed melody, the notes are validated by `parse`
+#[derive(Debug, Clone, Copy)]
+pub struct Rtttl<'a> {
+    pub name: &'a str,
+    duration: u32,
+    octave: u32,
+    bpm: u32,
+    notes: &'a str,
+}
+
+/// Parse and validate an RTTTL melody
+///
+/// # Examples
+/// ```
+/// use command_parser::rtttl::*;
+/// let rtttl = parse("beep:d=4,o=5,b=120:c,p,8a.").unwrap();
+/// assert_eq!(rtttl.name, "beep");
+/// let mut notes = rtttl.notes();
+/// assert_eq!(notes.next(), Some(Note { frequency_hz: 523, duration_ms: 500 }));
+/// assert_eq!(notes.next(), Some(Note { frequency_hz: 0, duration_ms: 500 }));
+/// assert_eq!(notes.next(), Some(Note { frequency_hz: 880, duration_ms: 375 }));
+/// assert_eq!(notes.next(), None);
+/// ```
+pub fn parse(text: &str) -> Result<Rtttl<'_>, Error> {
+    let mut sections = text.trim().splitn(3, ':');
+    let name = sections.next().ok_or(Error::MissingSection)?.trim();
+    let defaults = sections.next().ok_or(Error::MissingSection)?;
+    let notes = sections.next().ok_or(Error::MissingSection)?;
+
+    let mut rtttl = Rtttl {
+        name,
+        duration: 4,
+        octave: 6,
+        bpm: 63,
+        notes,
+    };
+    for default in defaults.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+        let (key, value) = default.split_once('=').ok_or(Error::InvalidDefault)?;
+        let value: u32 = value.trim().parse().map_err(|_| Error::InvalidDefault)?;
+        match key.trim() {
+            "d" if DURATIONS.contains(&value) => rtttl.duration = value,
+            "o" if OCTAVES.contains(&value) => rtttl.octave = value,
+            "b" if (1..=900).contains(&value) => rtttl.bpm = value,
+            _ => return Err(Error::InvalidDefault),
+        }
+    }
+
+    for (i, note) in rtttl.tokens().enumerate() {
+        rtttl.note(note).ok_or(Error::InvalidNote(i))?;
+    }
+    if rtttl.tokens().next().is_none() {
+        return Err(Error::MissingSection);
+    }
+    Ok(rtttl)
+}
+
+impl<'a> Rtttl<'a> {
+    fn tokens(&self) -> impl Iterator<Item = &'a str> {
+        self.notes
+            .split(',')
+            .map(str::trim)
+            .filter(|n| !n.is_empty())
+    }
+
+    /// Iterate over the notes of the melody
+    pub fn notes(&self) -> impl Iterator<Item = Note> + 'a {
+        let rtttl = *self;
+        // notes are validated by `parse`
+        self.tokens().filter_map(move |note| rtttl.note(note))
+    }
+
+    fn note(&self, token: &str) -> Option<Note> {
+        // notes are at most a few characters, e.g., `16c#.7`
+        let mut buf = [0u8; 8];
+        let note = buf.get_mut(..token.len())?;
+        note.copy_from_slice(token.as_bytes());
+        note.make_ascii_lowercase();
+
+        let (duration, rest) = number(note);
+        let duration = duration.unwrap_or(self.duration);
+        if !DURATIONS.contains(&duration) {
+            return None;
+        }
+
+        let (&letter, mut rest) = rest.split_first()?;
+        // semitones above c, None for a pause
+        let mut semitone = match letter {
+            b'c' => Some(0),
+            b'd' => Some(2),
+            b'e' => Some(4),
+            b'f' => Some(5),
+            b'g' => Some(7),
+            b'a' => Some(9),
+            b'b' | b'h' => Some(11),
+            b'p' => None,
+            _ => return None,
+        };
+        if let Some(r) = rest.strip_prefix(b"#") {
+            semitone = Some(semitone? + 1);
+            rest = r;
+        }
+        let mut dotted = false;
+        if let Some(r) = rest.strip_prefix(b".") {
+            dotted = true;
+            rest = r;
+        }
+        let (octave, mut rest) = number(rest);
+        let octave = octave.unwrap_or(self.octave);
+        if let Some(r) = rest.strip_prefix(b".") {
+            dotted = true;
+            rest = r;
+        }
+        if !rest.is_empty() || !OCTAVES.contains(&octave) {
+            return None;
+        }
+
+        // a whole note is four beats
+        let mut duration_ms = 240_000 / (self.bpm * duration);
+        if dotted {
+            duration_ms += duration_ms / 2;
+        }
+        // b# is c of the next octave
+        let frequency_hz = semitone.map_or(0, |s| {
+            ((OCTAVE_4[s % 12] << octave >> 4 << (s / 12)) + 50) / 100
+        });
+        Some(Note {
+            frequency_hz,
+            duration_ms,
+        })
+    }
+}
+
+/// Split a leading decimal number from `bytes`
+fn number(bytes: &[u8]) -> (Option<u32>, &[u8]) {
+    let len = bytes.iter().take_while(|b| b.is_ascii_digit()).count();
+    let (digits, rest) = bytes.split_at(len);
+    let value = core::str::from_utf8(digits)
+        .ok()
+        .and_then(|d| d.parse().ok());
+    (value, rest)
+}
+
+#[cfg(test)]
+mod test_rtttl {
+    use super::*;
+
+    fn notes(text: &str) -> Vec<(u32, u32)> {
+        parse(text)
+            .unwrap()
+            .notes()
+            .map(|n| (n.frequency_hz, n.duration_ms))
+            .collect()
+    }
+
+    #[test]
+    fn test_defaults() {
+        let rtttl = parse("x::a").unwrap();
+        assert_eq!(rtttl.name, "x");
+        // d=4, o=6, b=63
+        assert_eq!(notes("x::a"), [(1760, 952)]);
+    }
+
+    #[test]
+    fn test_frequencies() {
+        assert_eq!(
+            notes("x:d=4,o=4,b=60:c,c#,a,a5,a6,a7,c8,b3,b#"),
+            [
+                (262, 1000),
+                (277, 1000),
+                (440, 1000),
+                (880, 1000),
+                (1760, 1000),
+                (3520, 1000),
+                (4186, 1000),
+                (247, 1000),
+                (523, 1000)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_durations() {
+        assert_eq!(
+            notes("x:d=8,o=5,b=120:a,1a,2a,4a,16a,32a,a.,4a.,4a6.,4p."),
+            [
+                (880, 250),
+                (880, 2000),
+                (880, 1000),
+                (880, 500),
+                (880, 125),
+                (880, 62),
+                (880, 375),
+                (880, 750),
+                (1760, 750),
+                (0, 750)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_whitespace_and_case() {
+        assert_eq!(
+            notes(" x : d = 4 , o = 5 , b = 120 : C , 8P , E "),
+            [(523, 500), (0, 250), (659, 500)]
+        );
+    }
+
+    #[test]
+    fn test_builtin_style() {
+        let text = "Nokia:d=4,o=5,b=225:8e6,8d6,f#,g#,8c#6,8b,d,e,8b,8a,c#,e,2a";
+        let rtttl = parse(text).unwrap();
+        assert_eq!(rtttl.name, "Nokia");
+        assert_eq!(rtttl.notes().count(), 13);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(parse("").unwrap_err(), Error::MissingSection);
+        assert_eq!(parse("x:d=4").unwrap_err(), Error::MissingSection);
+        assert_eq!(parse("x:d=4:").unwrap_err(), Error::MissingSection);
+        assert_eq!(parse("x:d=3:a").unwrap_err(), Error::InvalidDefault);
+        assert_eq!(parse("x:o=9:a").unwrap_err(), Error::InvalidDefault);
+        assert_eq!(parse("x:b=0:a").unwrap_err(), Error::InvalidDefault);
+        assert_eq!(parse("x:q=1:a").unwrap_err(), Error::InvalidDefault);
+        assert_eq!(parse("x:d:a").unwrap_err(), Error::InvalidDefault);
+        assert_eq!(parse("x::a,x").unwrap_err(), Error::InvalidNote(1));
+        assert_eq!(parse("x::3a").unwrap_err(), Error::InvalidNote(0));
+        assert_eq!(parse("x::a9").unwrap_err(), Error::InvalidNote(0));
+        assert_eq!(parse("x::a#b").unwrap_err(), Error::InvalidNote(0));
+        assert_eq!(parse("x::p#").unwrap_err(), Error::InvalidNote(0));
+        assert_eq!(
+            parse("x::c,aaaaaaaaaaaaa").unwrap_err(),
+            Error::InvalidNote(1)
+        );
+    }
+}
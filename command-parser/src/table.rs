@@ -0,0 +1,1029 @@
+//! Command tables
+//!
+//! A command is declared by a `Spec`: its name, aliases, arguments and help
+//! text, and a `build` function turning the parsed arguments into a command.
+//! Parsing, range checking, the expected argument of a `Diagnostic`, help
+//! and tab completion are all derived from the specs, so adding a command
+//! means adding a row to a table.
+//!
+//! A `Registry` parses with a list of tables, so a module can declare its
+//! commands in a table of its own, see `COMMANDS` for the built-in ones.
+//!
+//! ```
+//! use command_parser::table::*;
+//! use command_parser::Error;
+//!
+//! #[derive(Debug, PartialEq)]
+//! enum Led {
+//!     On,
+//!     Off,
+//!     Blink(u32),
+//! }
+//!
+//! const LED: &[Spec<Led>] = &[
+//!     Spec::new("led on", "turn the LED on", |_| Ok(Led::On)),
+//!     Spec::new("led off", "turn the LED off", |_| Ok(Led::Off)),
+//!     Spec::new("led blink", "blink the LED", |v| Ok(Led::Blink(v.uint(0)?)))
+//!         .args(&[Arg::new("period", Kind::Uint { min: 10, max: 10_000 })
+//!             .unit("ms")
+//!             .help("time between toggles")]),
+//! ];
+//!
+//! let registry = Registry::new(&[LED]);
+//! assert_eq!(registry.parse(b"led blink 500"), Ok(Led::Blink(500)));
+//! let diagnostic = registry.parse(b"led blink 5").unwrap_err();
+//! assert_eq!(diagnostic.error, Error::ArgError);
+//! assert_eq!(diagnostic.expected.as_deref(), Some("10..=10000 ms"));
+//! let diagnostic = registry.parse(b"led dim").unwrap_err();
+//! assert_eq!(diagnostic.expected.as_deref(), Some("on, off or blink"));
+//! assert_eq!(registry.complete("led b"), ["blink"]);
+//! ```
+
+use core::fmt::{self, Write};
+
+use heapless::{String, Vec};
+
+use crate::args::{self, Invalid, TimeUnit};
+use crate::diagnostic::{Tokens, EXPECTED_LEN};
+use crate::{days_in_month, Diagnostic, Error, DAILY, DAY_NAMES, WEEKDAYS, WEEKEND};
+
+/// Last date the clock can hold, its time is kept in 32 bit seconds since
+/// 1970 which end on 2106-02-07 at 06:28:15
+const LAST_DATE: (u16, u8, u8) = (2106, 2, 7);
+
+/// Most arguments of a command
+pub const MAX_ARGS: usize = 4;
+/// Most choices offered for a subcommand or by `Registry::complete`
+pub const MAX_CHOICES: usize = 24;
+
+/// The type and range of an argument
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    /// Decimal or `0x` hex integer, `Value::Uint`
+    Uint { min: u32, max: u32 },
+    /// Decimal or `0x` hex integer with an optional sign, `Value::Int`
+    Int { min: i32, max: i32 },
+    /// Decimal number, `Value::Float`
+    Float { min: f32, max: f32 },
+    /// Decimal number with up to `decimals` decimals, `Value::Fixed` is the
+    /// number times `10^decimals`, so are `min` and `max`
+    Fixed { min: i32, max: i32, decimals: u8 },
+    /// Duration like `500ms`, `2s`, `5m` or `1h`, `Value::Uint` in `unit`,
+    /// which is also the unit of a bare number
+    Duration { min: u32, max: u32, unit: TimeUnit },
+    /// Frequency like `440`, `1k` or `2.5kHz`, `Value::Uint` in Hz
+    Frequency { min: u32, max: u32 },
+    /// Level in `0..=max`, or a percentage of `max` like `50%`,
+    /// `Value::Uint` is the level
+    Level { max: u32 },
+    /// `on`, `off`, `true`, `false`, `yes`, `no`, `1` or `0`, `Value::Bool`
+    Bool,
+    /// One of the words, `Value::Keyword` is the index of the word
+    Keyword(&'static [&'static str]),
+    /// `hh:mm:ss`, `Value::Time`, `Error::InvalidTime` if out of range
+    Time,
+    /// `hh:mm`, `Value::Time` with zero seconds
+    TimeOfDay,
+    /// `yyyy-mm-dd`, `Value::Date`, `Error::InvalidDate` if out of range,
+    /// 1970-01-01 to 2106-02-07
+    Date,
+    /// `daily`, `weekdays`, `weekend` or a list like `mon,wed,fri`,
+    /// `Value::Days` is the weekday mask
+    Days,
+    /// A word of at most `len` bytes, `Value::Text`
+    Text { len: usize },
+    /// The rest of the line, trimmed, of at most `len` bytes, `Value::Text`
+    Rest { len: usize },
+    /// The rest of the line, the words of a command, e.g., for `help`,
+    /// `Value::Text`
+    Command,
+}
+
+/// A parsed argument
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value<'a> {
+    Uint(u32),
+    Int(i32),
+    Float(f32),
+    Fixed(i32),
+    Bool(bool),
+    Keyword(usize),
+    Time { h: u8, m: u8, s: u8 },
+    Date { y: u16, mo: u8, d: u8 },
+    Days(u8),
+    Text(&'a str),
+}
+
+/// Split `arg` on `sep` into exactly `N` decimal fields
+fn fields<const N: usize>(arg: &str, sep: char) -> Result<[u16; N], Invalid> {
+    let mut out = [0; N];
+    let mut split = arg.split(sep);
+    for field in out.iter_mut() {
+        let next = split.next().ok_or(Invalid::Syntax)?;
+        *field = next.parse().map_err(|_| Invalid::Syntax)?;
+    }
+    match split.next() {
+        None => Ok(out),
+        _ => Err(Invalid::Syntax),
+    }
+}
+
+/// Parse a weekday spec, `daily`, `weekdays`, `weekend` or a comma
+/// separated list of day names (e.g., `mon,wed,fri`)
+fn parse_days(arg: &str) -> Option<u8> {
+    match arg {
+        "daily" => Some(DAILY),
+        "weekdays" => Some(WEEKDAYS),
+        "weekend" => Some(WEEKEND),
+        _ => arg.split(',').try_fold(0, |mask, day| {
+            let i = DAY_NAMES.iter().position(|name| *name == day)?;
+            Some(mask | 1 << i)
+        }),
+    }
+}
+
+impl Kind {
+    /// Parse and range check `token`, an `Invalid` tells why it failed and
+    /// converts to the `Error`
+    ///
+    /// # Examples
+    /// ```
+    /// use command_parser::args::{Invalid, TimeUnit};
+    /// use command_parser::table::*;
+    /// let kind = Kind::Int { min: -720, max: 840 };
+    /// assert_eq!(kind.parse("-330"), Ok(Value::Int(-330)));
+    /// assert_eq!(kind.parse("900"), Err(Invalid::TooHigh));
+    /// assert_eq!(Kind::Time.parse("24:00:00"), Err(Invalid::Time));
+    /// let kind = Kind::Duration { min: 1, max: 255, unit: TimeUnit::Minutes };
+    /// assert_eq!(kind.parse("5m"), Ok(Value::Uint(5)));
+    /// assert_eq!(Kind::Level { max: 255 }.parse("50%"), Ok(Value::Uint(128)));
+    /// ```
+    pub fn parse(self, token: &str) -> Result<Value<'_>, Invalid> {
+        match self {
+            Kind::Uint { min, max } => {
+                let v = args::uint(token)?;
+                args::in_range(v, min, max).map(Value::Uint)
+            }
+            Kind::Int { min, max } => {
+                let v = args::int(token)?;
+                args::in_range(v, min, max).map(Value::Int)
+            }
+            Kind::Float { min, max } => match token.parse::<f32>() {
+                Ok(v) if !v.is_nan() => args::in_range(v, min, max).map(Value::Float),
+                _ => Err(Invalid::Syntax),
+            },
+            Kind::Fixed { min, max, decimals } => {
+                let v = args::fixed(token, decimals)?;
+                args::in_range(v, min, max).map(Value::Fixed)
+            }
+            Kind::Duration { min, max, unit } => {
+                let v = args::duration(token, unit)?;
+                args::in_range(v, min, max).map(Value::Uint)
+            }
+            Kind::Frequency { min, max } => {
+                let v = args::frequency(token)?;
+                args::in_range(v, min, max).map(Value::Uint)
+            }
+            Kind::Level { max } => {
+                let v = match token.ends_with('%') {
+                    // tenths of a percent, rounded to the nearest level
+                    true => {
+                        let tenths = args::in_range(args::percent(token)?, 0, 1000)?;
+                        ((tenths as u64 * max as u64 + 500) / 1000) as u32
+                    }
+                    false => args::uint(token)?,
+                };
+                args::in_range(v, 0, max).map(Value::Uint)
+            }
+            Kind::Bool => args::boolean(token).map(Value::Bool),
+            Kind::Keyword(words) => match words.iter().position(|word| *word == token) {
+                Some(i) => Ok(Value::Keyword(i)),
+                None => Err(Invalid::Syntax),
+            },
+            Kind::Time => {
+                let [h, m, s] = fields(token, ':')?;
+                if h > 23 || m > 59 || s > 59 {
+                    return Err(Invalid::Time);
+                }
+                let (h, m, s) = (h as u8, m as u8, s as u8);
+                Ok(Value::Time { h, m, s })
+            }
+            Kind::TimeOfDay => {
+                let [h, m] = fields(token, ':')?;
+                if h > 23 || m > 59 {
+                    return Err(Invalid::Time);
+                }
+                let (h, m) = (h as u8, m as u8);
+                Ok(Value::Time { h, m, s: 0 })
+            }
+            Kind::Date => {
+                let [y, mo, d] = fields(token, '-')?;
+                if !(1970..=LAST_DATE.0).contains(&y) || !(1..=12).contains(&mo) {
+                    return Err(Invalid::Date);
+                }
+                let (mo, d) = (mo as u8, d.min(u8::MAX as u16) as u8);
+                if d == 0 || d > days_in_month(y, mo) || (y, mo, d) > LAST_DATE {
+                    return Err(Invalid::Date);
+                }
+                Ok(Value::Date { y, mo, d })
+            }
+            Kind::Days => parse_days(token).map(Value::Days).ok_or(Invalid::Syntax),
+            Kind::Text { len } | Kind::Rest { len } => match token.len() {
+                n if n > len => Err(Invalid::TooLong),
+                _ => Ok(Value::Text(token)),
+            },
+            Kind::Command => Ok(Value::Text(token)),
+        }
+    }
+}
+
+/// Write `words` as "a, b or c"
+fn write_choices<'w>(f: &mut impl Write, words: impl IntoIterator<Item = &'w str>) -> fmt::Result {
+    let mut words = words.into_iter().peekable();
+    let mut first = true;
+    while let Some(word) = words.next() {
+        match (first, words.peek()) {
+            (true, _) => {}
+            (false, Some(_)) => f.write_str(", ")?,
+            (false, None) => f.write_str(" or ")?,
+        }
+        f.write_str(word)?;
+        first = false;
+    }
+    Ok(())
+}
+
+/// What the argument should be, e.g., `0..=255`, `20Hz..=20kHz` or
+/// `c, f or k`
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Kind::Uint {
+                min: 0,
+                max: u32::MAX,
+            } => f.write_str("an integer"),
+            Kind::Uint { min, max } => write!(f, "{}..={}", min, max),
+            Kind::Int { min, max } => write!(f, "{}..={}", min, max),
+            Kind::Float { min, max } => write!(f, "{}..={}", min, max),
+            Kind::Fixed { min, max, decimals } => {
+                args::write_fixed(f, min, decimals)?;
+                f.write_str("..=")?;
+                args::write_fixed(f, max, decimals)
+            }
+            Kind::Duration { min, max, unit } => write!(f, "{}..={} {}", min, max, unit.name()),
+            Kind::Frequency {
+                min: 0,
+                max: u32::MAX,
+            } => f.write_str("a frequency like 440 or 2.5kHz"),
+            Kind::Frequency { min, max } => {
+                args::write_hz(f, min)?;
+                f.write_str("..=")?;
+                args::write_hz(f, max)
+            }
+            Kind::Level { max } => write!(f, "0..={} or 0%..=100%", max),
+            Kind::Bool => f.write_str("on or off"),
+            Kind::Keyword(words) => write_choices(f, words.iter().copied()),
+            Kind::Time => f.write_str("hh:mm:ss"),
+            Kind::TimeOfDay => f.write_str("hh:mm"),
+            Kind::Date => f.write_str("yyyy-mm-dd"),
+            Kind::Days => f.write_str("daily, weekdays, weekend or mon,wed,fri"),
+            Kind::Text { len } => write!(f, "a word of up to {} characters", len),
+            Kind::Rest { len } => write!(f, "text of up to {} characters", len),
+            Kind::Command => f.write_str("a command"),
+        }
+    }
+}
+
+/// An argument of a command
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Arg {
+    /// Name in the usage, e.g., `<name>`
+    pub name: &'static str,
+    pub kind: Kind,
+    /// Unit of a number, e.g., "minutes"
+    pub unit: &'static str,
+    /// An optional argument is skipped if it is missing, or if the token
+    /// is not one and another argument follows
+    pub optional: bool,
+    pub help: &'static str,
+}
+
+impl Arg {
+    pub const fn new(name: &'static str, kind: Kind) -> Self {
+        Self {
+            name,
+            kind,
+            unit: "",
+            optional: false,
+            help: "",
+        }
+    }
+
+    pub const fn unit(self, unit: &'static str) -> Self {
+        Self { unit, ..self }
+    }
+
+    pub const fn optional(self) -> Self {
+        Self {
+            optional: true,
+            ..self
+        }
+    }
+
+    pub const fn help(self, help: &'static str) -> Self {
+        Self { help, ..self }
+    }
+}
+
+/// What the argument should be, the kind and the unit, e.g.,
+/// `1..=255 minutes`
+impl fmt::Display for Arg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        match (self.kind, self.unit) {
+            (_, "") => Ok(()),
+            (
+                Kind::Uint {
+                    min: 0,
+                    max: u32::MAX,
+                },
+                unit,
+            ) => write!(f, " in {}", unit),
+            (_, unit) => write!(f, " {}", unit),
+        }
+    }
+}
+
+/// The parsed arguments of a command, by index in `Spec::args`
+#[derive(Debug, Default)]
+pub struct Values<'a> {
+    values: [Option<Value<'a>>; MAX_ARGS],
+}
+
+impl<'a> Values<'a> {
+    /// The argument at `i`, `None` if an optional argument was not given
+    pub fn get(&self, i: usize) -> Option<Value<'a>> {
+        self.values.get(i).copied().flatten()
+    }
+
+    pub fn uint(&self, i: usize) -> Result<u32, Error> {
+        match self.get(i) {
+            Some(Value::Uint(v)) => Ok(v),
+            _ => Err(Error::ArgMissing),
+        }
+    }
+
+    pub fn int(&self, i: usize) -> Result<i32, Error> {
+        match self.get(i) {
+            Some(Value::Int(v)) => Ok(v),
+            _ => Err(Error::ArgMissing),
+        }
+    }
+
+    pub fn float(&self, i: usize) -> Result<f32, Error> {
+        match self.get(i) {
+            Some(Value::Float(v)) => Ok(v),
+            _ => Err(Error::ArgMissing),
+        }
+    }
+
+    /// The number times `10^decimals` of a `Kind::Fixed`
+    pub fn fixed(&self, i: usize) -> Result<i32, Error> {
+        match self.get(i) {
+            Some(Value::Fixed(v)) => Ok(v),
+            _ => Err(Error::ArgMissing),
+        }
+    }
+
+    pub fn bool(&self, i: usize) -> Result<bool, Error> {
+        match self.get(i) {
+            Some(Value::Bool(v)) => Ok(v),
+            _ => Err(Error::ArgMissing),
+        }
+    }
+
+    pub fn keyword(&self, i: usize) -> Result<usize, Error> {
+        match self.get(i) {
+            Some(Value::Keyword(v)) => Ok(v),
+            _ => Err(Error::ArgMissing),
+        }
+    }
+
+    /// Hours, minutes and seconds
+    pub fn time(&self, i: usize) -> Result<(u8, u8, u8), Error> {
+        match self.get(i) {
+            Some(Value::Time { h, m, s }) => Ok((h, m, s)),
+            _ => Err(Error::ArgMissing),
+        }
+    }
+
+    /// Year, month and day
+    pub fn date(&self, i: usize) -> Result<(u16, u8, u8), Error> {
+        match self.get(i) {
+            Some(Value::Date { y, mo, d }) => Ok((y, mo, d)),
+            _ => Err(Error::ArgMissing),
+        }
+    }
+
+    pub fn days(&self, i: usize) -> Result<u8, Error> {
+        match self.get(i) {
+            Some(Value::Days(v)) => Ok(v),
+            _ => Err(Error::ArgMissing),
+        }
+    }
+
+    pub fn text(&self, i: usize) -> Result<&'a str, Error> {
+        match self.get(i) {
+            Some(Value::Text(v)) => Ok(v),
+            _ => Err(Error::ArgMissing),
+        }
+    }
+}
+
+/// A command of type `C`
+pub struct Spec<C> {
+    /// The words of the command, e.g., "alarm add"
+    pub name: &'static str,
+    pub aliases: &'static [&'static str],
+    pub args: &'static [Arg],
+    pub help: &'static str,
+    /// Build the command from the arguments, which are parsed and range
+    /// checked, errors point at the last argument
+    pub build: fn(&Values<'_>) -> Result<C, Error>,
+}
+
+impl<C> Spec<C> {
+    pub const fn new(
+        name: &'static str,
+        help: &'static str,
+        build: fn(&Values<'_>) -> Result<C, Error>,
+    ) -> Self {
+        Self {
+            name,
+            aliases: &[],
+            args: &[],
+            help,
+            build,
+        }
+    }
+
+    pub const fn aliases(self, aliases: &'static [&'static str]) -> Self {
+        Self { aliases, ..self }
+    }
+
+    pub const fn args(self, args: &'static [Arg]) -> Self {
+        assert!(args.len() <= MAX_ARGS);
+        Self { args, ..self }
+    }
+
+    /// The name followed by the arguments, `<required>` or `[optional]`
+    ///
+    /// # Examples
+    /// ```
+    /// use command_parser::*;
+    /// let spec = COMMANDS.find("alarm add").unwrap();
+    /// assert_eq!(spec.usage().to_string(), "alarm add <time> [days] [label]");
+    /// ```
+    pub fn usage(&self) -> Usage<'_, C> {
+        Usage(self)
+    }
+
+    /// Write the usage, the help text and a line per argument, e.g.,
+    ///
+    /// ``` text
+    /// usage: snooze <minutes>
+    /// snooze the ringing alarm
+    ///   minutes  1..=255 minutes
+    /// ```
+    pub fn write_help(&self, w: &mut impl Write) -> fmt::Result {
+        write!(w, "usage: {}\r\n", self.usage())?;
+        if !self.help.is_empty() {
+            write!(w, "{}\r\n", self.help)?;
+        }
+        let width = self.args.iter().map(|arg| arg.name.len()).max();
+        for arg in self.args {
+            write!(w, "  {}", arg.name)?;
+            pad(w, width.unwrap_or_default() - arg.name.len() + 2)?;
+            write!(w, "{}", arg)?;
+            if !arg.help.is_empty() {
+                write!(w, ", {}", arg.help)?;
+            }
+            w.write_str("\r\n")?;
+        }
+        if !self.aliases.is_empty() {
+            w.write_str("aliases: ")?;
+            write_choices(w, self.aliases.iter().copied())?;
+            w.write_str("\r\n")?;
+        }
+        Ok(())
+    }
+
+    /// Length of the usage line
+    fn usage_len(&self) -> usize {
+        let args = self.args.iter().map(|arg| arg.name.len() + 3);
+        self.name.len() + args.sum::<usize>()
+    }
+
+    /// The names and aliases of the command
+    fn names(&self) -> impl Iterator<Item = &'static str> {
+        core::iter::once(self.name).chain(self.aliases.iter().copied())
+    }
+}
+
+/// Usage line of a `Spec`, see `Spec::usage`
+pub struct Usage<'s, C>(&'s Spec<C>);
+
+impl<C> fmt::Display for Usage<'_, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0.name)?;
+        for arg in self.0.args {
+            match arg.optional {
+                true => write!(f, " [{}]", arg.name)?,
+                false => write!(f, " <{}>", arg.name)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A command type with a single table, e.g., from `#[derive(Command)]` of
+/// the `command-parser-derive` crate
+pub trait Commands: Sized + 'static {
+    const TABLE: &'static [Spec<Self>];
+
+    /// Parse a byte slice with `TABLE`, see `crate::parse_result`
+    fn parse_result(bytes: &[u8]) -> Result<Self, Error> {
+        Self::parse_diagnostic(bytes).map_err(|diagnostic| diagnostic.error)
+    }
+
+    /// Parse a byte slice with `TABLE`, see `crate::parse_diagnostic`
+    fn parse_diagnostic(bytes: &[u8]) -> Result<Self, Diagnostic<'_>> {
+        Registry::new(&[Self::TABLE]).parse(bytes)
+    }
+}
+
+/// Write `n` spaces
+fn pad(w: &mut impl Write, n: usize) -> fmt::Result {
+    (0..n).try_for_each(|_| w.write_char(' '))
+}
+
+/// Number of words of `name` that `words` start with, all of them or
+/// `None` if they differ
+fn matches<'a>(name: &str, mut words: impl Iterator<Item = &'a str>) -> Option<usize> {
+    name.split_whitespace()
+        .try_fold(0, |n, word| (words.next() == Some(word)).then_some(n + 1))
+}
+
+/// What a failed parse expected
+enum Expected {
+    Nothing,
+    /// An argument of the matched command
+    Arg(&'static Arg),
+    /// The next word of the commands starting with the words of the
+    /// command
+    Subcommand,
+}
+
+/// The commands of a list of tables
+pub struct Registry<'r, C: 'static> {
+    tables: &'r [&'static [Spec<C>]],
+}
+
+impl<'r, C> Registry<'r, C> {
+    pub const fn new(tables: &'r [&'static [Spec<C>]]) -> Self {
+        Self { tables }
+    }
+
+    /// All commands, in table order
+    pub fn specs(&self) -> impl Iterator<Item = &'static Spec<C>> + 'r {
+        self.tables.iter().flat_map(|table| table.iter())
+    }
+
+    /// The command named `name` or one of its aliases, words may be
+    /// separated by any whitespace
+    pub fn find(&self, name: &str) -> Option<&'static Spec<C>> {
+        let count = name.split_whitespace().count();
+        self.specs().find(|spec| {
+            spec.names().any(|alias| {
+                alias.split_whitespace().count() == count
+                    && matches(alias, name.split_whitespace()).is_some()
+            })
+        })
+    }
+
+    /// The commands whose names start with the words of `command`, all of
+    /// them if it is empty
+    pub fn matching<'c>(&self, command: &'c str) -> impl Iterator<Item = &'static Spec<C>> + 'c
+    where
+        'r: 'c,
+    {
+        self.specs()
+            .filter(move |spec| matches(command, spec.name.split_whitespace()).is_some())
+    }
+
+    /// Write the help of `command`, see `Spec::write_help`, or a usage line
+    /// and the help text of each command starting with its words, e.g., all
+    /// commands for "" or the alarm commands for "alarm"
+    ///
+    /// # Examples
+    /// ```
+    /// use command_parser::*;
+    /// let mut help = heapless::String::<256>::new();
+    /// COMMANDS.write_help(&mut help, "cal").unwrap();
+    /// assert_eq!(
+    ///     help,
+    ///     "cal add <celsius>  add a calibration point at the given temperature\r\n\
+    ///      cal fit            fit the model to the calibration points\r\n\
+    ///      cal show           show the model and calibration points\r\n\
+    ///      cal reset          restore the default model\r\n"
+    /// );
+    /// ```
+    pub fn write_help(&self, w: &mut impl Write, command: &str) -> fmt::Result {
+        if let Some(spec) = self.find(command) {
+            return spec.write_help(w);
+        }
+        let width = self.matching(command).map(Spec::usage_len).max();
+        for spec in self.matching(command) {
+            write!(w, "{}", spec.usage())?;
+            pad(w, width.unwrap_or_default() - spec.usage_len() + 2)?;
+            write!(w, "{}\r\n", spec.help)?;
+        }
+        Ok(())
+    }
+
+    /// Write the usage of the command of a `Diagnostic` for a missing
+    /// argument or too many arguments, nothing for other errors
+    ///
+    /// # Examples
+    /// ```
+    /// use command_parser::*;
+    /// let diagnostic = parse_diagnostic(b"snooze").unwrap_err();
+    /// let mut hint = heapless::String::<64>::new();
+    /// COMMANDS.write_hint(&mut hint, &diagnostic).unwrap();
+    /// assert_eq!(hint, "usage: snooze <minutes>\r\n");
+    /// ```
+    pub fn write_hint(&self, w: &mut impl Write, diagnostic: &Diagnostic) -> fmt::Result {
+        match diagnostic.error {
+            Error::ArgMissing | Error::ArgNumber if !diagnostic.command.is_empty() => {}
+            _ => return Ok(()),
+        }
+        match self.find(diagnostic.command) {
+            Some(spec) => write!(w, "usage: {}\r\n", spec.usage()),
+            // a missing subcommand
+            None => self
+                .matching(diagnostic.command)
+                .try_for_each(|spec| write!(w, "usage: {}\r\n", spec.usage())),
+        }
+    }
+
+    /// Parse a byte slice, on failure tell where and why, see `Diagnostic`
+    pub fn parse<'a>(&self, bytes: &'a [u8]) -> Result<C, Diagnostic<'a>> {
+        self.parse_line(bytes, true)
+    }
+
+    /// Parse like `parse`, but ignore any arguments after the last one
+    pub fn parse_prefix<'a>(&self, bytes: &'a [u8]) -> Result<C, Diagnostic<'a>> {
+        self.parse_line(bytes, false)
+    }
+
+    fn parse_line<'a>(&self, bytes: &'a [u8], strict: bool) -> Result<C, Diagnostic<'a>> {
+        // let's work on &str instead of raw byte arrays
+        let line = match core::str::from_utf8(bytes) {
+            Ok(line) => line,
+            Err(err) => {
+                // the valid part, to point at the first invalid byte
+                let line = core::str::from_utf8(&bytes[..err.valid_up_to()]).unwrap_or_default();
+                return Err(Tokens::new(line).diagnostic(Error::NonUtf8, None));
+            }
+        };
+        let mut tokens = Tokens::new(line);
+        let mut expected = Expected::Nothing;
+        self.parse_tokens(&mut tokens, &mut expected, strict)
+            .map_err(|error| {
+                let expected = self.expected(&tokens, expected);
+                tokens.diagnostic(error, expected)
+            })
+    }
+
+    fn parse_tokens(
+        &self,
+        tokens: &mut Tokens,
+        expected: &mut Expected,
+        strict: bool,
+    ) -> Result<C, Error> {
+        // the command with the most matching words
+        let mut best = None;
+        for spec in self.specs() {
+            for name in spec.names() {
+                if let Some(n) = matches(name, tokens.clone()) {
+                    if best.is_none_or(|(_, best)| n > best) {
+                        best = Some((spec, n));
+                    }
+                }
+            }
+        }
+        let Some((spec, words)) = best else {
+            return self.parse_subcommand(tokens, expected);
+        };
+        tokens.command();
+        for _ in 1..words {
+            tokens.next();
+            tokens.subcommand();
+        }
+
+        let mut values = Values::default();
+        for (i, arg) in spec.args.iter().enumerate() {
+            *expected = Expected::Arg(arg);
+            let token = match arg.kind {
+                Kind::Rest { .. } | Kind::Command => tokens.rest(),
+                _ => tokens.peek(),
+            };
+            match token.map(|token| arg.kind.parse(token)) {
+                None if arg.optional => {}
+                None => return Err(Error::ArgMissing),
+                Some(Ok(value)) => {
+                    tokens.next();
+                    values.values[i] = Some(value);
+                }
+                Some(Err(_)) if arg.optional && i + 1 < spec.args.len() => {}
+                Some(Err(invalid)) => {
+                    tokens.next();
+                    tokens.invalid(invalid);
+                    return Err(invalid.into());
+                }
+            }
+        }
+        let command = (spec.build)(&values)?;
+        match tokens.next() {
+            Some(_) if strict => Err(Error::ArgNumber),
+            _ => Ok(command),
+        }
+    }
+
+    /// The words are the start of commands but no command, e.g., `alarm`,
+    /// the next word is a missing or invalid subcommand
+    fn parse_subcommand(&self, tokens: &mut Tokens, expected: &mut Expected) -> Result<C, Error> {
+        let mut words = 0;
+        for name in self.specs().flat_map(Spec::names) {
+            let typed = tokens.clone().zip(name.split_whitespace());
+            words = words.max(typed.take_while(|(typed, word)| typed == word).count());
+        }
+        tokens.command();
+        if words == 0 {
+            return Err(Error::CommandNotFound);
+        }
+        for _ in 1..words {
+            tokens.next();
+            tokens.subcommand();
+        }
+        *expected = Expected::Subcommand;
+        match tokens.next() {
+            Some(_) => Err(Error::ArgError),
+            None => Err(Error::ArgMissing),
+        }
+    }
+
+    /// The words starting with `partial` that may follow the words of a
+    /// command, names only
+    fn next_words<'a>(
+        &self,
+        command: impl Iterator<Item = &'a str> + Clone,
+        partial: &str,
+    ) -> Vec<&'static str, MAX_CHOICES> {
+        let mut words = Vec::new();
+        for spec in self.specs() {
+            let mut name = spec.name.split_whitespace();
+            if !command.clone().all(|word| name.next() == Some(word)) {
+                continue;
+            }
+            if let Some(word) = name.next().filter(|word| word.starts_with(partial)) {
+                if !words.contains(&word) {
+                    let _ = words.push(word);
+                }
+            }
+        }
+        words
+    }
+
+    fn expected(&self, tokens: &Tokens, expected: Expected) -> Option<String<EXPECTED_LEN>> {
+        let mut out = String::new();
+        match expected {
+            Expected::Nothing => return None,
+            Expected::Arg(arg) => write!(out, "{}", arg),
+            Expected::Subcommand => write_choices(
+                &mut out,
+                self.next_words(tokens.command_str().split_whitespace(), ""),
+            ),
+        }
+        .ok()?;
+        Some(out)
+    }
+
+    /// Completions of the last, partial, word of `line`, the next words of
+    /// the matching commands or the keywords of the argument
+    ///
+    /// # Examples
+    /// ```
+    /// use command_parser::*;
+    /// assert_eq!(COMMANDS.complete("al"), ["alarm"]);
+    /// assert_eq!(COMMANDS.complete("alarm d"), ["del", "disable"]);
+    /// assert_eq!(COMMANDS.complete("units "), ["c", "f", "k"]);
+    /// ```
+    pub fn complete(&self, line: &str) -> Vec<&'static str, MAX_CHOICES> {
+        let line = line.trim_start();
+        let (done, partial) = line.rsplit_once(char::is_whitespace).unwrap_or(("", line));
+        let mut choices = self.next_words(done.split_whitespace(), partial);
+        // the keywords of the argument after a complete command, or the
+        // words of the command given as the last argument
+        let words = done.split_whitespace().count();
+        let spec = self
+            .specs()
+            .filter_map(|spec| matches(spec.name, done.split_whitespace()).map(|n| (spec, n)))
+            .max_by_key(|(_, n)| *n);
+        if let Some((spec, n)) = spec {
+            if let Some(Kind::Keyword(keywords)) = spec.args.get(words - n).map(|arg| arg.kind) {
+                for keyword in keywords
+                    .iter()
+                    .filter(|keyword| keyword.starts_with(partial))
+                {
+                    let _ = choices.push(keyword);
+                }
+            }
+            if let Some(Kind::Command) = spec.args.last().map(|arg| arg.kind) {
+                let skip = n + spec.args.len() - 1;
+                if words >= skip {
+                    let command = done.split_whitespace().skip(skip);
+                    choices.extend(self.next_words(command, partial));
+                }
+            }
+        }
+        choices
+    }
+}
+
+#[cfg(test)]
+mod test_table {
+    use super::*;
+    use crate::*;
+
+    #[test]
+    fn test_find() {
+        assert_eq!(COMMANDS.find("duty").unwrap().name, "duty");
+        assert_eq!(COMMANDS.find("alarm  rm").unwrap().name, "alarm del");
+        assert!(COMMANDS.find("alarm").is_none());
+        assert!(COMMANDS.find("alarm add 07:30").is_none());
+    }
+
+    #[test]
+    fn test_aliases() {
+        assert_eq!(parse_result(b"alarm ls"), Ok(Command::AlarmList));
+        assert_eq!(parse_result(b"alarm rm 2"), Ok(Command::AlarmDel(2)));
+    }
+
+    #[test]
+    fn test_expected() {
+        let expected = |line: &str| {
+            let diagnostic = parse_diagnostic(line.as_bytes()).unwrap_err();
+            diagnostic
+                .expected
+                .as_deref()
+                .map(std::string::String::from)
+        };
+        assert_eq!(
+            expected("freq x").as_deref(),
+            Some("a frequency like 440 or 2.5kHz")
+        );
+        assert_eq!(expected("snooze 0").as_deref(), Some("1..=255 minutes"));
+        assert_eq!(
+            expected("alarm").as_deref(),
+            Some("add, list, del, enable or disable")
+        );
+        assert_eq!(
+            expected("cal x").as_deref(),
+            Some("add, fit, show or reset")
+        );
+        assert_eq!(expected("temp").as_deref(), Some("history"));
+        assert_eq!(expected("units x").as_deref(), Some("c, f or k"));
+        assert_eq!(expected("bogus"), None);
+    }
+
+    #[test]
+    fn test_usage() {
+        let usage = |name| COMMANDS.find(name).unwrap().usage().to_string();
+        assert_eq!(usage("time"), "time [time]");
+        assert_eq!(usage("tz"), "tz <offset>");
+        assert_eq!(usage("stop"), "stop");
+    }
+
+    #[test]
+    fn test_complete() {
+        assert_eq!(
+            COMMANDS.complete("s"),
+            ["start", "stop", "snooze", "sensors", "save"]
+        );
+        assert_eq!(
+            COMMANDS.complete("  cal  "),
+            ["add", "fit", "show", "reset"]
+        );
+        assert_eq!(COMMANDS.complete("units k"), ["k"]);
+        assert!(COMMANDS.complete("bogus ").is_empty());
+        assert!(COMMANDS.complete("duty 1").is_empty());
+        assert_eq!(COMMANDS.complete("").len(), 19);
+        assert_eq!(COMMANDS.complete("help al"), ["alarm"]);
+        assert_eq!(COMMANDS.complete("help alarm e"), ["enable"]);
+    }
+
+    fn help(command: &str) -> std::string::String {
+        let mut help = std::string::String::new();
+        COMMANDS.write_help(&mut help, command).unwrap();
+        help
+    }
+
+    #[test]
+    fn test_help() {
+        assert_eq!(
+            help("alarm  rm"),
+            "usage: alarm del <index>\r\n\
+             delete an alarm\r\n  \
+             index  0..=255, from alarm list\r\n\
+             aliases: alarm rm\r\n"
+        );
+        assert_eq!(
+            help("tz"),
+            "usage: tz <offset>\r\n\
+             set the offset from UTC\r\n  \
+             offset  -720..=840 minutes, e.g., 60 for UTC+1\r\n"
+        );
+        // a line per command, aligned
+        let all = help("");
+        assert_eq!(all.lines().count(), COMMANDS.specs().count());
+        assert!(all.starts_with("freq <hz>     "));
+        assert!(all.contains("\r\nhelp [command]  "));
+        assert_eq!(help("bogus"), "");
+    }
+
+    #[test]
+    fn test_help_command() {
+        assert_eq!(parse_result(b"help"), Ok(Command::Help(String::new())));
+        assert_eq!(
+            parse_result(b"help alarm  add"),
+            Ok(Command::Help("alarm add".parse().unwrap()))
+        );
+        assert_eq!(
+            parse_result(b"help alarm    disable"),
+            Ok(Command::Help("alarm disable".parse().unwrap()))
+        );
+        assert_eq!(
+            parse_result(b"help \tfactory-reset"),
+            Ok(Command::Help("factory-reset".parse().unwrap()))
+        );
+        assert_eq!(
+            parse_result(b"help alarm"),
+            Ok(Command::Help("alarm".parse().unwrap()))
+        );
+        let diagnostic = parse_diagnostic(b"help bogus").unwrap_err();
+        assert_eq!(
+            (diagnostic.error, diagnostic.span),
+            (Error::ArgError, 5..10)
+        );
+        assert_eq!(diagnostic.expected.as_deref(), Some("a command"));
+    }
+
+    #[test]
+    fn test_hint() {
+        let hint = |line: &str| {
+            let diagnostic = parse_diagnostic(line.as_bytes()).unwrap_err();
+            let mut hint = std::string::String::new();
+            COMMANDS.write_hint(&mut hint, &diagnostic).unwrap();
+            hint
+        };
+        assert_eq!(hint("duty"), "usage: duty <duty>\r\n");
+        assert_eq!(hint("alarm  rm 1 2"), "usage: alarm del <index>\r\n");
+        assert_eq!(
+            hint("cal"),
+            "usage: cal add <celsius>\r\n\
+             usage: cal fit\r\n\
+             usage: cal show\r\n\
+             usage: cal reset\r\n"
+        );
+        assert_eq!(hint("duty 800"), "");
+        assert_eq!(hint("bogus"), "");
+    }
+
+    #[test]
+    fn test_parse_prefix() {
+        assert_eq!(
+            COMMANDS.parse_prefix(b"alarm add 07:30 daily work extra"),
+            Ok(Command::AlarmAdd {
+                h: 7,
+                m: 30,
+                days: DAILY,
+                label: "work".parse().unwrap()
+            })
+        );
+        assert_eq!(
+            COMMANDS.parse_prefix(b"duty 800 x").unwrap_err().error,
+            Error::ArgError
+        );
+    }
+}
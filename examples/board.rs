@@ -0,0 +1,147 @@
+#![no_std]
+
+use crate::hal;
+use hal::gpio::{p0, p1, Disconnected, Input, Level, Output, Pin, PullUp, PushPull};
+use hal::pac::{self, CLOCK, GPIOTE, NVMC, P0, P1, PWM0, PWM1, RTC0, SAADC, SPIM0, TEMP, USBD};
+
+// Implements `Pins::new` from a pin table, the port names given first
+// are the ones used in the table
+macro_rules! pin_map {
+    (
+        ($port0:ident, $port1:ident)
+        $($name:ident: $port:ident.$pin:ident => $mode:ident,)*
+    ) => {
+        impl Pins {
+            pub fn new(p0: P0, p1: P1) -> Self {
+                let $port0 = p0::Parts::new(p0);
+                let $port1 = p1::Parts::new(p1);
+                Self {
+                    $($name: pin_map!(@$mode $port.$pin),)*
+                }
+            }
+        }
+    };
+    (@output $pin:expr) => { $pin.into_push_pull_output(Level::Low).degrade() };
+    (@output_high $pin:expr) => { $pin.into_push_pull_output(Level::High).degrade() };
+    (@pullup $pin:expr) => { $pin.into_pullup_input().degrade() };
+    (@analog $pin:expr) => { $pin };
+}
+
+// Pin tables, one per hardware revision. Each entry is
+// `name: port.pin => mode`, where mode is one of
+// - output, push pull output starting low
+// - output_high, push pull output starting high
+// - pullup, input with pull up
+// - analog, kept as is for the SAADC, its type is `ThermistorPin`
+
+// Alarm clock PCB, nRF52833
+#[cfg(feature = "52833")]
+pub const NAME: &str = "alarm clock PCB";
+#[cfg(feature = "52833")]
+pub type ThermistorPin = p0::P0_03<Disconnected>;
+#[cfg(feature = "52833")]
+pin_map! {
+    (port0, port1)
+    display_sck: port0.p0_05 => output,
+    display_mosi: port0.p0_04 => output,
+    display_rst: port0.p0_31 => output,
+    display_cs: port0.p0_11 => output,
+    display_dc: port1.p1_09 => output,
+    led: port0.p0_09 => output,
+    button_toggle: port0.p0_20 => pullup,
+    button_brighten: port0.p0_29 => pullup,
+    button_dimmer: port0.p0_30 => pullup,
+    buzzer_a: port0.p0_28 => output,
+    buzzer_b: port0.p0_02 => output,
+    thermistor: port0.p0_03 => analog,
+}
+
+// nRF52840 DK, with the display on the Arduino header (D8-D13), LED1,
+// buttons 1-3, the buzzer on D6/D7 and the thermistor on A0
+#[cfg(feature = "52840")]
+pub const NAME: &str = "nRF52840 DK";
+#[cfg(feature = "52840")]
+pub type ThermistorPin = p0::P0_03<Disconnected>;
+#[cfg(feature = "52840")]
+pin_map! {
+    (port0, port1)
+    display_sck: port1.p1_15 => output,
+    display_mosi: port1.p1_13 => output,
+    display_rst: port1.p1_10 => output,
+    display_cs: port1.p1_12 => output,
+    display_dc: port1.p1_11 => output,
+    // LED1 is active low
+    led: port0.p0_13 => output_high,
+    button_toggle: port0.p0_11 => pullup,
+    button_brighten: port0.p0_12 => pullup,
+    button_dimmer: port0.p0_24 => pullup,
+    buzzer_a: port1.p1_08 => output,
+    buzzer_b: port1.p1_07 => output,
+    thermistor: port0.p0_03 => analog,
+}
+
+// The pins used by the drivers, every revision must give all of them
+pub struct Pins {
+    pub display_sck: Pin<Output<PushPull>>,
+    pub display_mosi: Pin<Output<PushPull>>,
+    pub display_rst: Pin<Output<PushPull>>,
+    pub display_cs: Pin<Output<PushPull>>,
+    pub display_dc: Pin<Output<PushPull>>,
+    pub led: Pin<Output<PushPull>>,
+    pub button_toggle: Pin<Input<PullUp>>,
+    pub button_brighten: Pin<Input<PullUp>>,
+    pub button_dimmer: Pin<Input<PullUp>>,
+    pub buzzer_a: Pin<Output<PushPull>>,
+    pub buzzer_b: Pin<Output<PushPull>>,
+    pub thermistor: ThermistorPin,
+}
+
+
+// All peripherals used by the alarm clock
+pub struct Board {
+    pub pins: Pins,
+    pub spim: SPIM0,
+    pub led_pwm: PWM0,
+    pub buzzer_pwm: PWM1,
+    pub saadc: SAADC,
+    pub temp: TEMP,
+    pub rtc: RTC0,
+    pub clock: CLOCK,
+    pub usbd: USBD,
+    pub nvmc: NVMC,
+    pub gpiote: GPIOTE,
+}
+
+impl Board {
+    pub fn new(device: pac::Peripherals) -> Self {
+        let pac::Peripherals {
+            SAADC,
+            TEMP,
+            RTC0,
+            PWM0,
+            PWM1,
+            SPIM0,
+            P0,
+            P1,
+            CLOCK,
+            USBD,
+            NVMC,
+            GPIOTE,
+            ..
+        } = device;
+
+        Self {
+            pins: Pins::new(P0, P1),
+            spim: SPIM0,
+            led_pwm: PWM0,
+            buzzer_pwm: PWM1,
+            saadc: SAADC,
+            temp: TEMP,
+            rtc: RTC0,
+            clock: CLOCK,
+            usbd: USBD,
+            nvmc: NVMC,
+            gpiote: GPIOTE,
+        }
+    }
+}
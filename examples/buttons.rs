@@ -0,0 +1,66 @@
+#![no_std]
+
+use crate::hal;
+use hal::gpio::{Input, Pin, PullUp};
+use hal::gpiote::Gpiote;
+use hal::pac::GPIOTE;
+use embedded_hal::digital::v2::InputPin;
+use clock_core::button::{Button, Event};
+use heapless::Deque;
+
+// Buttons sensed by the GPIOTE PORT event. Every pin senses the level
+// opposite to its current one, so any press or release raises the event.
+pub struct Buttons {
+    gpiote: Gpiote,
+    pins: [Pin<Input<PullUp>>; 3],
+    buttons: [Button; 3],
+    // button index and event, until taken by `next_event`
+    events: Deque<(usize, Event), 8>,
+}
+
+impl Buttons {
+    pub fn new(gpiote: GPIOTE, pins: [Pin<Input<PullUp>>; 3]) -> Self {
+        let gpiote = Gpiote::new(gpiote);
+        for pin in &pins {
+            gpiote.port().input_pin(pin).low();
+        }
+        gpiote.port().reset_events();
+        gpiote.port().enable_interrupt();
+
+        Self {
+            gpiote,
+            pins,
+            buttons: Default::default(),
+            events: Deque::new(),
+        }
+    }
+
+    // Sample all buttons at `now` (ms) and rearm the sense, returns the ms
+    // until the buttons must be sampled again without an edge
+    pub fn sample(&mut self, now: u32) -> Option<u32> {
+        // clear first, an edge while rearming raises a new event
+        self.gpiote.port().reset_events();
+
+        let mut next: Option<u32> = None;
+        for (i, (pin, button)) in self.pins.iter().zip(&mut self.buttons).enumerate() {
+            let pressed = pin.is_low().unwrap();
+            if pressed {
+                self.gpiote.port().input_pin(pin).high();
+            } else {
+                self.gpiote.port().input_pin(pin).low();
+            }
+            if let Some(event) = button.update(pressed, now) {
+                // drop events if nobody takes them
+                let _ = self.events.push_back((i, event));
+            }
+            if let Some(ms) = button.next_update(now) {
+                next = Some(next.map_or(ms, |next| next.min(ms)));
+            }
+        }
+        next
+    }
+
+    pub fn next_event(&mut self) -> Option<(usize, Event)> {
+        self.events.pop_front()
+    }
+}
@@ -1,32 +1,119 @@
 #![no_std]
 
-use nrf52833_hal as hal;
+use crate::hal;
 use hal::gpio::{Output, Pin, PushPull};
-use embedded_hal::digital::v2::OutputPin;
-use cortex_m::asm::delay;
+use hal::pac::PWM1;
+use hal::pwm::{Channel, Prescaler, Pwm};
+use hal::time::Hertz;
 
+/// Audible range accepted by `set_frequency`
+pub const MIN_HZ: u32 = 20;
+pub const MAX_HZ: u32 = 20_000;
+/// PWM counter clock before prescaling
+const PWM_CLOCK_HZ: u32 = 16_000_000;
+/// Largest counter top supported by the PWM (15 bits)
+const MAX_TOP: u32 = 32_767;
+
+/// Buzzer commands from the serial link, sent to the `buzzer_ctrl` task
+/// instead of the much larger `Command`
+#[derive(Clone, Copy, Debug)]
+pub enum Control {
+    Frequency(u32),
+    Duty(u8),
+    Start,
+    Stop,
+}
+
+/// Piezo buzzer driven differentially by two PWM channels, the second
+/// channel is inverted so the buzzer sees twice the supply swing.
+///
+/// Tones are started and stopped without blocking, timing is left to the
+/// caller (an RTIC task scheduling `stop_tone`).
 pub struct Buzzer {
-    buzzer_1: Pin<Output<PushPull>>,
-    buzzer_2: Pin<Output<PushPull>>,
+    pwm: Pwm<PWM1>,
+    frequency: u32,
+    // 0..=255, 255 is a 50% duty square wave
+    volume: u8,
+    playing: bool,
 }
 
 impl Buzzer {
     pub fn new(
+        pwm: PWM1,
         buzzer_1: Pin<Output<PushPull>>,
         buzzer_2: Pin<Output<PushPull>>,
     ) -> Self {
-        Buzzer { buzzer_1, buzzer_2 }
+        let pwm = Pwm::new(pwm);
+        pwm.set_output_pin(Channel::C0, buzzer_1);
+        pwm.set_output_pin(Channel::C1, buzzer_2);
+
+        Buzzer {
+            pwm,
+            frequency: 2000,
+            volume: u8::MAX,
+            playing: false,
+        }
     }
 
-    pub fn beep(&mut self, duration: u32) {
-        for _ in 0..duration {
-            self.buzzer_1.set_high().ok();
-            self.buzzer_2.set_low().ok();
-            delay(500_000);
+    /// Set the tone frequency, returns false for frequencies out of range
+    pub fn set_frequency(&mut self, hz: u32) -> bool {
+        if !(MIN_HZ..=MAX_HZ).contains(&hz) {
+            return false;
+        }
+        self.frequency = hz;
+        if self.playing {
+            self.start_tone(hz);
+        }
+        true
+    }
 
-            self.buzzer_1.set_low().ok();
-            self.buzzer_2.set_high().ok();
-            delay(500_000);
+    pub fn frequency(&self) -> u32 {
+        self.frequency
+    }
+
+    /// Set the volume as duty, 255 is loudest and 0 is silent
+    pub fn set_volume(&mut self, volume: u8) {
+        self.volume = volume;
+        if self.playing {
+            self.set_duty();
         }
     }
+
+    /// Start a tone at `hz` (clamped to the audible range), it keeps
+    /// playing until `stop_tone`
+    pub fn start_tone(&mut self, hz: u32) {
+        let hz = hz.clamp(MIN_HZ, MAX_HZ);
+        // smallest prescaler that fits the period in the 15 bit counter
+        let prescaler = [
+            Prescaler::Div1,
+            Prescaler::Div2,
+            Prescaler::Div4,
+            Prescaler::Div8,
+            Prescaler::Div16,
+            Prescaler::Div32,
+            Prescaler::Div64,
+            Prescaler::Div128,
+        ]
+        .into_iter()
+        .enumerate()
+        .find(|(shift, _)| PWM_CLOCK_HZ >> shift <= MAX_TOP * hz)
+        .map_or(Prescaler::Div128, |(_, prescaler)| prescaler);
+
+        self.pwm.set_prescaler(prescaler);
+        self.pwm.set_period(Hertz(hz));
+        self.set_duty();
+        self.pwm.enable();
+        self.playing = true;
+    }
+
+    pub fn stop_tone(&mut self) {
+        self.pwm.disable();
+        self.playing = false;
+    }
+
+    fn set_duty(&self) {
+        let duty = (self.pwm.max_duty() as u32 * self.volume as u32 / (2 * u8::MAX as u32)) as u16;
+        self.pwm.set_duty_on(Channel::C0, duty);
+        self.pwm.set_duty_off(Channel::C1, duty);
+    }
 }
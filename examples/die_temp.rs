@@ -0,0 +1,22 @@
+#![no_std]
+
+use crate::hal::pac::TEMP;
+use crate::hal::temp::Temp;
+use clock_core::sensor::Celsius;
+
+// The on-die temperature sensor, 0.25 °C resolution and about ±5 °C
+// accuracy, a second reading to cross-check the thermistor with
+pub struct DieTemp {
+    temp: Temp,
+}
+
+impl DieTemp {
+    pub fn new(temp: TEMP) -> Self {
+        Self { temp: Temp::new(temp) }
+    }
+
+    // Blocks for the conversion, about 36 µs
+    pub fn read_temperature(&mut self) -> Celsius {
+        self.temp.measure().to_num()
+    }
+}
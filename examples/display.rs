@@ -1,17 +1,10 @@
 #![no_std]
 
-use nrf52833_hal::{
+use crate::hal::{
     pac::SPIM0,
-    gpio::{Output, PushPull, Level, Pin, p0::{P0_04, P0_05, P0_11, P0_31}, p1::P1_09},
+    gpio::{Output, PushPull, Level, Pin},
     spim::{Spim, Frequency, MODE_0},
 };
-use embedded_graphics::{
-    mono_font::{ascii::FONT_10X20, MonoTextStyle},
-    pixelcolor::BinaryColor,
-    prelude::*,
-    text::Text,
-    geometry::Point,
-};
 use ssd1306::{
     prelude::*,
     Ssd1306,
@@ -20,33 +13,32 @@ use ssd1306::{
     rotation::DisplayRotation,
 };
 use display_interface_spi::SPIInterface;
-use heapless::String;
 use embedded_hal::digital::v2::OutputPin;
-use core::fmt::Write;
-
-#[derive(Clone, Copy)]
-pub enum MenuState {
-    Clock,
-    Temperature,
-}
+use clock_core::alarm::State;
+use clock_core::menu::{Input, Menu, Response, Values};
+use clock_core::sensor::SensorError;
+use clock_core::units::TemperatureUnit;
+use clock_core::draw;
+use clock_core::history::{History, HISTORY_LEN};
+use clock_core::view::View;
 
 pub struct Display {
     display: Ssd1306<
-        SPIInterface<Spim<SPIM0>, P1_09<Output<PushPull>>, P0_11<Output<PushPull>>>,
+        SPIInterface<Spim<SPIM0>, Pin<Output<PushPull>>, Pin<Output<PushPull>>>,
         DisplaySize128x32,
         BufferedGraphicsMode<DisplaySize128x32>,
     >,
-    menu_state: MenuState,
+    view: View,
 }
 
 impl Display {
     pub fn new(
         spim0: SPIM0,
-        sck: P0_05<Output<PushPull>>,
-        mosi: P0_04<Output<PushPull>>,
-        dc: P1_09<Output<PushPull>>,
-        rst: &mut P0_31<Output<PushPull>>,
-        cs: P0_11<Output<PushPull>>,
+        sck: Pin<Output<PushPull>>,
+        mosi: Pin<Output<PushPull>>,
+        dc: Pin<Output<PushPull>>,
+        rst: &mut Pin<Output<PushPull>>,
+        cs: Pin<Output<PushPull>>,
     ) -> Self {
         let _ = rst.set_low();
         cortex_m::asm::delay(10_000);
@@ -54,9 +46,9 @@ impl Display {
 
         let spim = Spim::new(
             spim0,
-            nrf52833_hal::spim::Pins {
-                sck: Some(sck.into()),
-                mosi: Some(mosi.into()),
+            crate::hal::spim::Pins {
+                sck: Some(sck),
+                mosi: Some(mosi),
                 miso: None,
             },
             Frequency::M4,
@@ -73,54 +65,74 @@ impl Display {
 
         Self {
             display,
-            menu_state: MenuState::Clock,
+            view: View::default(),
         }
     }
 
-    pub fn update_menu(&mut self) {
-        match self.menu_state {
-            MenuState::Clock => self.show_time(12, 30, 45),
-            MenuState::Temperature => self.show_temperature(25.3),
+    pub fn menu(&self) -> &Menu {
+        self.view.menu()
+    }
+
+    /// Pass a button to the menu, `current` holds the values of the
+    /// current page
+    pub fn input(&mut self, input: Input, current: &Values) -> Response {
+        self.view.input(input, current)
+    }
+
+    /// Draw the current page, `values` holds its field values and is
+    /// ignored while editing
+    pub fn update_menu(&mut self, values: &Values) {
+        self.view.set_values(values);
+        self.redraw();
+    }
+
+    /// Cache the time, drawn if the clock is shown
+    pub fn set_time(&mut self, hours: u32, minutes: u32, seconds: u32) {
+        if self.view.set_time(hours as u8, minutes as u8, seconds as u8) {
+            self.redraw();
         }
     }
 
-    pub fn next_menu(&mut self) {
-        self.menu_state = match self.menu_state {
-            MenuState::Clock => MenuState::Temperature,
-            MenuState::Temperature => MenuState::Clock,
-        };
+    /// Cache the temperature in °C and add it to the history, drawn if its
+    /// page is shown
+    pub fn set_temperature(&mut self, temp: f32) {
+        if self.view.set_temperature(temp) {
+            self.redraw();
+        }
     }
 
-    pub fn show_time(&mut self, hours: u32, minutes: u32, seconds: u32) {
-        let mut buffer: String<16> = String::new();
-        let _ = write!(buffer, "{:02}:{:02}:{:02}", hours, minutes, seconds);
+    /// Show a sensor fault instead of the temperature, drawn if its page is
+    /// shown
+    pub fn set_fault(&mut self, fault: SensorError) {
+        if self.view.set_fault(fault) {
+            self.redraw();
+        }
+    }
 
-        let _ = self.display.clear();
-        let style = MonoTextStyle::new(&FONT_10X20, BinaryColor::On);
-        let _ = Text::new(&buffer, Point::new(20, 20), style).draw(&mut self.display);
-        let _ = self.display.flush();
+    /// Show a ringing or snoozed alarm with its snooze count, back to the
+    /// current menu page when idle
+    pub fn set_alarm(&mut self, state: State) {
+        if self.view.set_alarm(state) {
+            self.redraw();
+        }
     }
 
-    pub fn show_temperature(&mut self, temp: f32) {
-        let mut buffer: String<16> = String::new();
-        let _ = write!(buffer, "Temp: {:.1} C", temp);
+    pub fn history(&self) -> &History<HISTORY_LEN> {
+        self.view.history()
+    }
 
-        let _ = self.display.clear();
-        let style = MonoTextStyle::new(&FONT_10X20, BinaryColor::On);
-        let _ = Text::new(&buffer, Point::new(20, 20), style).draw(&mut self.display);
-        let _ = self.display.flush();
+    pub fn unit(&self) -> TemperatureUnit {
+        self.view.unit()
     }
 
-    pub fn show_settings(&mut self) {
-        let buffer = "Settings";
+    pub fn set_unit(&mut self, unit: TemperatureUnit) {
+        self.view.set_unit(unit);
+        self.redraw();
+    }
 
+    fn redraw(&mut self) {
         let _ = self.display.clear();
-        let style = MonoTextStyle::new(&FONT_10X20, BinaryColor::On);
-        let _ = Text::new(buffer, Point::new(30, 20), style).draw(&mut self.display);
+        let _ = draw::draw(&self.view.screen(), &mut self.display);
         let _ = self.display.flush();
     }
-
-    pub fn get_menu_state(&self) -> MenuState {
-        self.menu_state
-    }
 }
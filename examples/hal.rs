@@ -0,0 +1,21 @@
+#![no_std]
+
+// The nRF HAL selected by the `52833` (default) or `52840` feature, the
+// other modules use `crate::hal` instead of naming a chip
+
+#[cfg(all(feature = "52833", feature = "52840"))]
+compile_error!("enable one of the `52833` and `52840` features, e.g., `--no-default-features --features 52840`");
+#[cfg(not(any(feature = "52833", feature = "52840")))]
+compile_error!("enable one of the `52833` and `52840` features");
+
+#[cfg(feature = "52833")]
+pub use nrf52833_hal::*;
+#[cfg(feature = "52840")]
+pub use nrf52840_hal::*;
+
+// Settings are kept in the last two 4 kB pages of the flash, settings.x
+// fails the link if the image grows into them
+#[cfg(feature = "52833")]
+pub const SETTINGS_FLASH: usize = 0x8_0000 - 2 * 4096;
+#[cfg(feature = "52840")]
+pub const SETTINGS_FLASH: usize = 0x10_0000 - 2 * 4096;
@@ -1,6 +1,6 @@
 #![no_std]
 
-use nrf52833_hal as hal;
+use crate::hal;
 use hal::gpio::{Level, Output, Pin, PushPull};
 use hal::pwm::*;
 use hal::pac::PWM0;
@@ -36,6 +36,15 @@ impl Led {
         }
     }
 
+    pub fn brightness(&self) -> u16 {
+        self.duty
+    }
+
+    pub fn set_brightness(&mut self, duty: u16) {
+        self.duty = duty.min(MAX_DUTY);
+        self.pwm.set_duty_off(Channel::C0, self.duty);
+    }
+
     pub fn brighten(&mut self) {
         if self.duty + DUTY_STEP <= MAX_DUTY {
             self.duty += DUTY_STEP;
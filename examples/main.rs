@@ -6,24 +6,41 @@ use panic_rtt_target as _;
 use rtt_target::{rprintln, rtt_init_print};
 
 // Import modules
+mod hal;
+mod board;
 mod led;
 mod serial;
 mod thermistor;
+mod die_temp;
 mod buzzer;
 mod display;
 mod rtc;
+mod melody;
+mod storage;
+mod buttons;
 
-#[rtic::app(device = nrf52833_hal::pac, dispatchers = [TIMER0])]
+#[rtic::app(device = crate::hal::pac, dispatchers = [TIMER0])]
 mod app {
     use super::*;
     use systick_monotonic::{fugit, Systick};
-    use nrf52833_hal::{pac, gpio::p0::Parts as P0Parts};
-    use nrf52833_hal::gpio::p1::Parts as P1Parts;
-    use nrf52833_hal::gpio::Level;
     use fugit::ExtU32;
-    use embedded_hal::digital::v2::InputPin;
+    use crate::buzzer::Control;
+    use command_parser::{Command, Unit, COMMANDS, DAY_NAMES};
+    use clock_core::alarm::{Action, Alarm, AlarmState};
+    use clock_core::button::Event;
+    use clock_core::calendar::days_in_month;
+    use clock_core::menu::{Input, Page, Response, Values};
+    use clock_core::sensor::{cross_check, FilterConfig};
+    use clock_core::settings::Settings;
+    use clock_core::thermistor::{Calibration, Model, Reference};
+    use clock_core::units::{Temperature, TemperatureUnit};
+    use core::fmt::Write;
 
     const TIMER_HZ: u32 = 1000; // 1ms precision
+    // index in `buttons::Buttons`
+    const TOGGLE: usize = 0;
+    const BRIGHTEN: usize = 1;
+    const DIMMER: usize = 2;
 
     #[monotonic(binds = SysTick, default = true)]
     type MyMono = Systick<TIMER_HZ>;
@@ -32,20 +49,24 @@ mod app {
     struct Shared {
         serial: serial::Serial,
         rtc: rtc::RTC,
-        display: display::Display
+        display: display::Display,
+        led: led::Led,
+        buzzer: buzzer::Buzzer,
+        alarm: AlarmState,
+        player: melody::Player,
+        thermistor: thermistor::Thermistor,
+        die_temp: die_temp::DieTemp,
+        buttons: buttons::Buttons,
+        button_handle: Option<button_task::SpawnHandle>,
     }
 
     #[local]
     struct Local {
-        led: led::Led,
-        buzzer: buzzer::Buzzer,
-        thermistor: thermistor::Thermistor,
-        button_toggle: nrf52833_hal::gpio::Pin<nrf52833_hal::gpio::Input<nrf52833_hal::gpio::PullUp>>,
-        button_brighten: nrf52833_hal::gpio::Pin<nrf52833_hal::gpio::Input<nrf52833_hal::gpio::PullUp>>,
-        button_dimmer: nrf52833_hal::gpio::Pin<nrf52833_hal::gpio::Input<nrf52833_hal::gpio::PullUp>>,
-        prev_toggle: bool,
-        prev_brighten: bool,
-        prev_dimmer: bool,
+        store: storage::SettingsStore,
+        // reference points entered with `cal add`
+        calibration: Calibration,
+        // the press started while an alarm was active
+        alarm_press: [bool; 3],
     }
 
     #[init]
@@ -58,162 +79,573 @@ mod app {
 
         let mono = Systick::new(cp.SYST, 64_000_000);
        
-        let pac::Peripherals {
-            SAADC,
-            RTC0,
-            PWM0,
-            SPIM0,
-            P0,
-            P1,
-            CLOCK,
-            USBD,
-            ..
-        } = cx.device;
-        
-        // Initiera P0 och P1 delar
-        let port0 = P0Parts::new(P0);
-        let port1 = P1Parts::new(P1);
+        // Peripherals and pins of the selected board
+        let board::Board { mut pins, spim, led_pwm, buzzer_pwm, saadc, temp, rtc: rtc0, clock, usbd, nvmc, gpiote } =
+            board::Board::new(cx.device);
+        rprintln!("Board: {}", board::NAME);
         
         // Display
-        let sck  = port0.p0_05.into_push_pull_output(Level::Low);
-        let mosi = port0.p0_04.into_push_pull_output(Level::Low);
-        let mut rst = port0.p0_31.into_push_pull_output(Level::Low);
-        let cs   = port0.p0_11.into_push_pull_output(Level::Low);
-        let dc   = port1.p1_09.into_push_pull_output(Level::Low);
-        
-        
-        let display = display::Display::new(
-            SPIM0,
-            sck,   
-            mosi,  
-            dc,    
-            &mut rst, 
-            cs,    
+        let mut display = display::Display::new(
+            spim,
+            pins.display_sck,
+            pins.display_mosi,
+            pins.display_dc,
+            &mut pins.display_rst,
+            pins.display_cs,
         );
         
         // LED
-        let led_pin = port0.p0_09.into_push_pull_output(Level::Low);
-        let led = led::Led::new(PWM0, led_pin.into());
+        let mut led = led::Led::new(led_pwm, pins.led);
         
         // Serial via USB
-        let serial = serial::Serial::new(CLOCK, USBD);
+        let serial = serial::Serial::new(clock, usbd);
         
         // SAADC and Thermistor
-        let saadc_config = nrf52833_hal::saadc::SaadcConfig {
-            resolution: nrf52833_hal::saadc::Resolution::_12BIT,
-            oversample: nrf52833_hal::saadc::Oversample::OVER8X,
+        let saadc_config = hal::saadc::SaadcConfig {
+            resolution: hal::saadc::Resolution::_12BIT,
+            oversample: hal::saadc::Oversample::OVER8X,
             ..Default::default()
         };
-        let saadc = nrf52833_hal::saadc::Saadc::new(SAADC, saadc_config);
-        let sensor_pin = port0.p0_03; 
-        let thermistor = thermistor::Thermistor::new(saadc, sensor_pin);
+        let saadc = hal::saadc::Saadc::new(saadc, saadc_config);
+        let mut thermistor = thermistor::Thermistor::new(saadc, pins.thermistor, FilterConfig::default());
+        let die_temp = die_temp::DieTemp::new(temp);
         
         // Buzzer
-        let buzzer = buzzer::Buzzer::new(
-            port0.p0_28.into_push_pull_output(Level::Low).into(),
-            port0.p0_02.into_push_pull_output(Level::High).into(),
-        );
+        let buzzer = buzzer::Buzzer::new(buzzer_pwm, pins.buzzer_a, pins.buzzer_b);
         
         
         // RTC
-        let rtc = rtc::RTC::new(RTC0, unsafe { serial::CLOCKS.as_ref().unwrap() }, &mut cp.NVIC);
+        let mut rtc = rtc::RTC::new(rtc0, unsafe { serial::CLOCKS.as_ref().unwrap() }, &mut cp.NVIC);
+
+        // Settings from flash
+        let mut store = storage::new(nvmc);
+        let settings = store.load().unwrap_or_else(|| {
+            rprintln!("No valid settings, using defaults");
+            Settings::default()
+        });
+        let mut alarm = AlarmState::default();
+        apply_settings(settings, &mut rtc, &mut led, &mut thermistor, &mut display, &mut alarm);
 
-        
-        // Buttons
-        let button_toggle = port0.p0_20.into_pullup_input().into();
-        let button_brighten = port0.p0_29.into_pullup_input().into();
-        let button_dimmer  = port0.p0_30.into_pullup_input().into();
-        
         read_temperature::spawn_after(1000_u32.millis().into()).unwrap();
         poll_serial::spawn_after(10_u32.millis().into()).unwrap();
-        button_poll::spawn_after(50_u32.millis().into()).unwrap();
-        beep_task::spawn_after(5000_u32.millis().into()).unwrap();
+
+        // Buttons, sampled on edges
+        let buttons = buttons::Buttons::new(
+            gpiote,
+            [pins.button_toggle, pins.button_brighten, pins.button_dimmer],
+        );
         
         (
-            Shared { serial, rtc, display },
-            Local {
+            Shared {
+                serial,
+                rtc,
+                display,
                 led,
                 buzzer,
+                alarm,
+                player: melody::Player::new(),
                 thermistor,
-                button_toggle,
-                button_brighten,
-                button_dimmer,
-                prev_toggle: true,
-                prev_brighten: true,
-                prev_dimmer: true,
+                die_temp,
+                buttons,
+                button_handle: None,
+            },
+            Local {
+                store,
+                calibration: Calibration::default(),
+                alarm_press: [false; 3],
             },
             init::Monotonics(mono),
         )
     }
 
-    // Periodic temperature measurement
-    #[task(shared = [display], local = [thermistor])]
+    // Periodic temperature measurement, averaged into the history and
+    // cross-checked with the on-die sensor
+    #[task(shared = [display, thermistor, die_temp])]
     fn read_temperature(mut cx: read_temperature::Context) {
-        let temp = cx.local.thermistor.read_temperature();
-        rprintln!("Temperature: {:.2} °C", temp);
-
-        cx.shared.display.lock(|display| {
-            if let display::MenuState::Temperature = display.get_menu_state() {
-                display.show_temperature(temp);
+        let temp = cx.shared.thermistor.lock(|thermistor| thermistor.read_temperature());
+        match temp {
+            Ok(temp) => {
+                let die = cx.shared.die_temp.lock(|die_temp| die_temp.read_temperature());
+                cx.shared.display.lock(|display| {
+                    let unit = display.unit();
+                    rprintln!("Temperature: {:.2}", Temperature::new(temp, unit));
+                    if !cross_check(temp, die) {
+                        rprintln!("Thermistor and die differ, die: {:.2}", Temperature::new(die, unit));
+                    }
+                    display.set_temperature(temp);
+                });
             }
-    });
+            Err(fault) => {
+                rprintln!("Sensor fault: {}", fault.name());
+                cx.shared.display.lock(|display| display.set_fault(fault));
+            }
+        }
 
     read_temperature::spawn_after(1000_u32.millis().into()).unwrap();
 }
 
-    // Get serial input
-    #[task(shared = [serial])]
+    // Get serial input and dispatch complete command lines
+    #[task(shared = [serial, rtc, alarm, player, led, thermistor, die_temp, display], local = [store, calibration])]
     fn poll_serial(mut cx: poll_serial::Context) {
-        cx.shared.serial.lock(|serial| serial.poll());
+        let rtc = &mut cx.shared.rtc;
+        let alarm = &mut cx.shared.alarm;
+        let player = &mut cx.shared.player;
+        let led = &mut cx.shared.led;
+        let thermistor = &mut cx.shared.thermistor;
+        let die_temp = &mut cx.shared.die_temp;
+        let display = &mut cx.shared.display;
+        let store = cx.local.store;
+        let calibration = cx.local.calibration;
+        cx.shared.serial.lock(|serial| {
+            serial.poll(|command, reply| match command {
+                Command::FrequencyHz(hz) if !(buzzer::MIN_HZ..=buzzer::MAX_HZ).contains(&hz) => {
+                    Err("FrequencyRange")
+                }
+                Command::FrequencyHz(hz) => buzzer_ctrl::spawn(Control::Frequency(hz)).map_err(|_| "Busy"),
+                Command::Duty(volume) => buzzer_ctrl::spawn(Control::Duty(volume)).map_err(|_| "Busy"),
+                Command::Start => buzzer_ctrl::spawn(Control::Start).map_err(|_| "Busy"),
+                Command::Stop => buzzer_ctrl::spawn(Control::Stop).map_err(|_| "Busy"),
+                Command::SetTime { h, m, s } => rtc
+                    .lock(|rtc| rtc.set_time(h, m, s))
+                    .then_some(())
+                    .ok_or("DateRange"),
+                Command::SetDate { y, mo, d } => rtc
+                    .lock(|rtc| rtc.set_date(y, mo, d))
+                    .then_some(())
+                    .ok_or("DateRange"),
+                Command::GetTime => {
+                    let now = rtc.lock(|rtc| rtc.get_datetime());
+                    let _ = write!(reply, "{} {}\r\n", now, now.weekday().name());
+                    Ok(())
+                }
+                Command::AlarmAdd { h, m, days, label } => {
+                    let alarm = Alarm {
+                        hour: h,
+                        minute: m,
+                        enabled: true,
+                        weekdays: days,
+                        label,
+                    };
+                    let i = rtc.lock(|rtc| rtc.add_alarm(alarm)).ok_or("AlarmTableFull")?;
+                    let _ = write!(reply, "alarm {}\r\n", i);
+                    Ok(())
+                }
+                Command::AlarmList => {
+                    rtc.lock(|rtc| {
+                        for (i, alarm) in rtc.alarms() {
+                            let _ = write!(
+                                reply,
+                                "{} {:02}:{:02} {} ",
+                                i,
+                                alarm.hour,
+                                alarm.minute,
+                                if alarm.enabled { "on " } else { "off" }
+                            );
+                            for (day, name) in DAY_NAMES.iter().enumerate() {
+                                let name = if alarm.weekdays & 1 << day != 0 { name } else { "---" };
+                                let _ = write!(reply, "{} ", name);
+                            }
+                            let _ = write!(reply, "{}\r\n", alarm.label);
+                        }
+                    });
+                    Ok(())
+                }
+                Command::AlarmDel(i) => rtc
+                    .lock(|rtc| rtc.remove_alarm(i as usize))
+                    .then_some(())
+                    .ok_or("NoSuchAlarm"),
+                Command::AlarmEnable(i) | Command::AlarmDisable(i) => {
+                    let enabled = matches!(command, Command::AlarmEnable(_));
+                    rtc.lock(|rtc| rtc.enable_alarm(i as usize, enabled))
+                        .then_some(())
+                        .ok_or("NoSuchAlarm")
+                }
+                Command::SnoozeMinutes(minutes) => {
+                    alarm.lock(|alarm| alarm.set_snooze_minutes(minutes));
+                    Ok(())
+                }
+                Command::Play(name) => {
+                    let melody = melody::find(&name).ok_or("NoSuchMelody")?;
+                    if player.lock(|player| player.start(melody, false)) {
+                        melody_task::spawn().map_err(|_| {
+                            player.lock(|player| player.spawn_failed());
+                            "Busy"
+                        })?;
+                    }
+                    Ok(())
+                }
+                Command::Melody(melody) => {
+                    if player.lock(|player| player.start(&melody, false)) {
+                        melody_task::spawn().map_err(|_| {
+                            player.lock(|player| player.spawn_failed());
+                            "Busy"
+                        })?;
+                    }
+                    Ok(())
+                }
+                Command::TimeZone(minutes) => {
+                    rtc.lock(|rtc| rtc.set_tz_offset(minutes));
+                    Ok(())
+                }
+                Command::TempHistory => {
+                    // copied out so the display is not locked while the host reads
+                    let history = display.lock(|display| display.history().clone());
+                    history.write_csv(reply).map_err(|_| "WriteError")
+                }
+                Command::Help(command) => COMMANDS.write_help(reply, &command).map_err(|_| "WriteError"),
+                Command::Sensors => {
+                    // one unfiltered sample, so that the temperature matches the
+                    // resistance and the display's moving average is left alone
+                    let (reading, vdd) = thermistor.lock(|thermistor| {
+                        let reading = thermistor
+                            .read_resistance()
+                            .map(|ohms| (thermistor.celsius(ohms), ohms));
+                        (reading, thermistor.read_vdd())
+                    });
+                    let temp = reading.map(|(temp, _)| temp);
+                    let die = die_temp.lock(|die_temp| die_temp.read_temperature());
+                    let unit = display.lock(|display| display.unit());
+                    match reading {
+                        Ok((temp, ohms)) => {
+                            let temp = Temperature::new(temp, unit);
+                            let _ = write!(reply, "thermistor {:.2} {:.0} ohm\r\n", temp, ohms);
+                        }
+                        Err(fault) => {
+                            let _ = write!(reply, "thermistor {}\r\n", fault.name());
+                        }
+                    }
+                    let _ = write!(reply, "die {:.2}\r\n", Temperature::new(die, unit));
+                    let _ = match vdd {
+                        Ok(vdd) => write!(reply, "vdd {:.2} V\r\n", vdd),
+                        Err(fault) => write!(reply, "vdd {}\r\n", fault.name()),
+                    };
+                    if let Ok(temp) = temp {
+                        let check = if cross_check(temp, die) { "ok" } else { "mismatch" };
+                        let _ = write!(reply, "check {}\r\n", check);
+                    }
+                    Ok(())
+                }
+                Command::Units(unit) => {
+                    let unit = match unit {
+                        Unit::Celsius => TemperatureUnit::Celsius,
+                        Unit::Fahrenheit => TemperatureUnit::Fahrenheit,
+                        Unit::Kelvin => TemperatureUnit::Kelvin,
+                    };
+                    display.lock(|display| display.set_unit(unit));
+                    Ok(())
+                }
+                Command::CalAdd(celsius) => {
+                    let ohms = thermistor
+                        .lock(|thermistor| thermistor.read_resistance())
+                        .map_err(|fault| fault.name())?;
+                    if !calibration.add(Reference { celsius, ohms }) {
+                        return Err("CalibrationFull");
+                    }
+                    let _ = write!(reply, "point {} {:.0} ohm\r\n", calibration.references().len(), ohms);
+                    Ok(())
+                }
+                Command::CalFit => {
+                    let model = thermistor.lock(|thermistor| thermistor.model());
+                    let model = calibration.fit(&model).map_err(|_| "CalibrationError")?;
+                    // the fit replaces the offset
+                    thermistor.lock(|thermistor| {
+                        thermistor.set_model(model);
+                        thermistor.set_calibration(0);
+                    });
+                    calibration.clear();
+                    let _ = write!(reply, "{}\r\n", model);
+                    Ok(())
+                }
+                Command::CalShow => {
+                    let (model, offset) =
+                        thermistor.lock(|thermistor| (thermistor.model(), thermistor.calibration()));
+                    let _ = write!(reply, "{}\r\noffset {:.2} C\r\n", model, offset as f32 / 100.0);
+                    for (i, point) in calibration.references().iter().enumerate() {
+                        let _ = write!(reply, "point {} {:.2} C {:.0} ohm\r\n", i + 1, point.celsius, point.ohms);
+                    }
+                    Ok(())
+                }
+                Command::CalReset => {
+                    thermistor.lock(|thermistor| {
+                        thermistor.set_model(Model::default());
+                        thermistor.set_calibration(0);
+                    });
+                    calibration.clear();
+                    Ok(())
+                }
+                Command::Save => {
+                    let settings = (&mut *rtc, &mut *led, &mut *thermistor, &mut *display, &mut *alarm)
+                        .lock(|rtc, led, thermistor, display, alarm| {
+                            collect_settings(rtc, led, thermistor, display, alarm)
+                        });
+                    store.save(&settings).map_err(|_| "FlashError")
+                }
+                Command::Load | Command::FactoryReset => {
+                    let settings = if let Command::Load = command {
+                        store.load().ok_or("NoSettings")?
+                    } else {
+                        store.erase().map_err(|_| "FlashError")?;
+                        Settings::default()
+                    };
+                    (&mut *rtc, &mut *led, &mut *thermistor, &mut *display, &mut *alarm)
+                        .lock(|rtc, led, thermistor, display, alarm| {
+                            apply_settings(settings, rtc, led, thermistor, display, alarm)
+                        });
+                    Ok(())
+                }
+            })
+        });
         poll_serial::spawn_after(10_u32.millis().into()).unwrap();
     }
 
+    // Buzzer commands from the serial link, `start` plays a continuous
+    // tone at the frequency and volume set by `freq` and `duty`
+    #[task(shared = [buzzer, player], capacity = 4)]
+    fn buzzer_ctrl(cx: buzzer_ctrl::Context, control: Control) {
+        (cx.shared.buzzer, cx.shared.player).lock(|buzzer, player| match control {
+            Control::Frequency(hz) => {
+                buzzer.set_frequency(hz);
+            }
+            Control::Duty(volume) => buzzer.set_volume(volume),
+            Control::Start => buzzer.start_tone(buzzer.frequency()),
+            Control::Stop => {
+                player.stop();
+                buzzer.stop_tone();
+            }
+        });
+    }
+
+    // Play a tone for `ms` milliseconds without blocking, a new tone
+    // replaces the one playing
+    #[task(shared = [buzzer], local = [tone_handle: Option<tone_off::SpawnHandle> = None], capacity = 4)]
+    fn play_tone(mut cx: play_tone::Context, hz: u32, ms: u32) {
+        cx.shared.buzzer.lock(|buzzer| buzzer.start_tone(hz));
+        let handle = cx.local.tone_handle.take();
+        *cx.local.tone_handle = match handle.map(|handle| handle.reschedule_after(ms.millis().into())) {
+            Some(Ok(handle)) => Some(handle),
+            _ => tone_off::spawn_after(ms.millis().into()).ok(),
+        };
+    }
+
+    #[task(shared = [buzzer])]
+    fn tone_off(mut cx: tone_off::Context) {
+        cx.shared.buzzer.lock(|buzzer| buzzer.stop_tone());
+    }
+
     // RTC Interrupt Handler
-    #[task(binds = RTC0, shared = [rtc, display])]
+    #[task(binds = RTC0, shared = [rtc, display, alarm, buzzer, player])]
     fn rtc_handler(mut cx: rtc_handler::Context) {
-        cx.shared.rtc.lock(|rtc| rtc.handle_interrupt());
+        let due = cx.shared.rtc.lock(|rtc| {
+            let due = rtc.handle_interrupt();
+            if let Some(alarm) = due.and_then(|i| rtc.alarm(i)) {
+                rprintln!("Alarm {}", alarm.label);
+            }
+            due
+        });
+
+        let (action, state) = cx.shared.alarm.lock(|alarm| {
+            let action = match due {
+                Some(i) => alarm.trigger(i),
+                None => alarm.tick(),
+            };
+            (action, alarm.state())
+        });
+        (&mut cx.shared.player, &mut cx.shared.buzzer)
+            .lock(|player, buzzer| alarm_action(action, player, buzzer));
 
         let (hours, minutes, seconds) = cx.shared.rtc.lock(|rtc| rtc.get_time());
 
         cx.shared.display.lock(|display| {
-            if let display::MenuState::Clock = display.get_menu_state() {
-                display.show_time(hours, minutes, seconds);
-            }
+            display.set_time(hours, minutes, seconds);
+            display.set_alarm(state);
         });
     }
 
-    // Button polling task for menu navigation and LED control
-    #[task(shared = [display], local = [button_toggle, button_brighten, button_dimmer, prev_toggle, prev_brighten, prev_dimmer, led])]
-    fn button_poll(mut cx: button_poll::Context) {
-        let toggle_state = cx.local.button_toggle.is_high().unwrap();
-        let brighten_state = cx.local.button_brighten.is_high().unwrap();
-        let dimmer_state = cx.local.button_dimmer.is_high().unwrap();
+    // A button edge raised the GPIOTE PORT event, sample the buttons and
+    // let `button_task` debounce
+    #[task(binds = GPIOTE, shared = [buttons, button_handle])]
+    fn button_edge(mut cx: button_edge::Context) {
+        let now = monotonics::now().ticks() as u32;
+        let next = cx.shared.buttons.lock(|buttons| buttons.sample(now));
+        cx.shared
+            .button_handle
+            .lock(|handle| schedule_button_task(handle, next.unwrap_or(0)));
+    }
 
-        if *cx.local.prev_toggle && !toggle_state {
-            cx.shared.display.lock(|display| {
-                display.next_menu();
-                display.update_menu();
+    // Button events for menu navigation and LED control, a press that
+    // starts while an alarm is active snoozes (click) or dismisses (long press).
+    // Toggle moves to the next page or field and selects with a long press,
+    // brighten/dimmer change the edited field or else the LED.
+    // Runs while a button is debouncing or held, and stops when idle.
+    #[task(shared = [buttons, button_handle, display, led, rtc, alarm, buzzer, player], local = [alarm_press])]
+    fn button_task(mut cx: button_task::Context) {
+        let now = monotonics::now().ticks() as u32;
+        let next = cx.shared.buttons.lock(|buttons| buttons.sample(now));
+
+        while let Some((i, event)) = cx.shared.buttons.lock(|buttons| buttons.next_event()) {
+            let alarm_press = &mut cx.local.alarm_press[i];
+            if let Event::Press | Event::DoubleClick = event {
+                *alarm_press = cx.shared.alarm.lock(|alarm| alarm.is_active());
+            }
+
+            if *alarm_press {
+                let transition: fn(&mut AlarmState) -> Action = match event {
+                    Event::Release => AlarmState::snooze,
+                    Event::LongPress => AlarmState::dismiss,
+                    // the click that snoozed, not for the menu
+                    Event::Click => {
+                        *alarm_press = false;
+                        continue;
+                    }
+                    _ => continue,
+                };
+                let (action, state) = cx.shared.alarm.lock(|alarm| (transition(alarm), alarm.state()));
+                (&mut cx.shared.player, &mut cx.shared.buzzer)
+                    .lock(|player, buzzer| alarm_action(action, player, buzzer));
+                cx.shared.display.lock(|display| display.set_alarm(state));
+                continue;
+            }
+
+            let input = match (i, event) {
+                // a click once it is neither long nor double
+                (TOGGLE, Event::Click) => Input::Next,
+                (TOGGLE, Event::LongPress) => Input::Select,
+                (TOGGLE, Event::DoubleClick) => {
+                    cx.shared.led.lock(|led| led.toggle());
+                    continue;
+                }
+                (BRIGHTEN, Event::Press | Event::DoubleClick | Event::Held) => Input::Up,
+                (DIMMER, Event::Press | Event::DoubleClick | Event::Held) => Input::Down,
+                _ => continue,
+            };
+
+            (&mut cx.shared.display, &mut cx.shared.led, &mut cx.shared.rtc).lock(|display, led, rtc| {
+                let current = page_values(display.menu().page(), rtc, led, display);
+                match display.input(input, &current) {
+                    Response::Ignored => match input {
+                        Input::Up => led.brighten(),
+                        Input::Down => led.dim(),
+                        _ => {}
+                    },
+                    Response::Changed => {}
+                    Response::Commit(page, values) => commit_page(page, &values, rtc, led, display),
+                }
+                let current = page_values(display.menu().page(), rtc, led, display);
+                display.update_menu(&current);
             });
         }
-        if *cx.local.prev_brighten && !brighten_state {
-            cx.local.led.brighten();
+
+        if let Some(ms) = next {
+            cx.shared.button_handle.lock(|handle| schedule_button_task(handle, ms));
+        }
+    }
+
+    // Run `button_task` in `ms`, moving it if already scheduled
+    fn schedule_button_task(handle: &mut Option<button_task::SpawnHandle>, ms: u32) {
+        let delay = ms.max(1).millis().into();
+        *handle = match handle.take().map(|handle| handle.reschedule_after(delay)) {
+            Some(Ok(handle)) => Some(handle),
+            _ => button_task::spawn_after(delay).ok(),
+        };
+    }
+
+    // Field values of a menu page
+    fn page_values(page: Page, rtc: &rtc::RTC, led: &led::Led, display: &display::Display) -> Values {
+        match page {
+            Page::Time => {
+                let (h, m, s) = rtc.get_time();
+                [h as i32, m as i32, s as i32]
+            }
+            Page::Date => {
+                let now = rtc.get_datetime();
+                [now.year as i32, now.month as i32, now.day as i32]
+            }
+            Page::Brightness => [led.brightness() as i32 / 10, 0, 0],
+            Page::Units => [display.unit() as i32, 0, 0],
+            Page::Alarms => [rtc.alarms().filter(|(_, alarm)| alarm.enabled).count() as i32, 0, 0],
+            _ => [0; 3],
+        }
+    }
+
+    // Apply the values of an edited menu page
+    fn commit_page(page: Page, values: &Values, rtc: &mut rtc::RTC, led: &mut led::Led, display: &mut display::Display) {
+        let [a, b, c] = *values;
+        match page {
+            Page::Time => {
+                rtc.set_time(a as u8, b as u8, c as u8);
+            }
+            Page::Date => {
+                let (year, month) = (a as u16, b as u8);
+                rtc.set_date(year, month, (c as u8).min(days_in_month(year, month)));
+            }
+            Page::Brightness => led.set_brightness(a as u16 * 10),
+            Page::Units => display.set_unit(TemperatureUnit::from_index(a as u8).unwrap_or(TemperatureUnit::Celsius)),
+            _ => {}
         }
-        if *cx.local.prev_dimmer && !dimmer_state {
-            cx.local.led.dim();
+    }
+
+    // Gather the persistent settings from the peripherals that own them
+    fn collect_settings(
+        rtc: &rtc::RTC,
+        led: &led::Led,
+        thermistor: &thermistor::Thermistor,
+        display: &display::Display,
+        alarm: &AlarmState,
+    ) -> Settings {
+        Settings {
+            alarms: rtc.alarm_table().clone(),
+            brightness: led.brightness(),
+            tz_offset_minutes: rtc.tz_offset(),
+            unit: display.unit(),
+            calibration: thermistor.calibration(),
+            model: thermistor.model(),
+            snooze_minutes: alarm.snooze_minutes(),
         }
+    }
 
-        *cx.local.prev_toggle = toggle_state;
-        *cx.local.prev_brighten = brighten_state;
-        *cx.local.prev_dimmer = dimmer_state;
+    fn apply_settings(
+        settings: Settings,
+        rtc: &mut rtc::RTC,
+        led: &mut led::Led,
+        thermistor: &mut thermistor::Thermistor,
+        display: &mut display::Display,
+        alarm: &mut AlarmState,
+    ) {
+        rtc.set_alarm_table(settings.alarms);
+        rtc.set_tz_offset(settings.tz_offset_minutes);
+        led.set_brightness(settings.brightness);
+        display.set_unit(settings.unit);
+        thermistor.set_calibration(settings.calibration);
+        thermistor.set_model(settings.model);
+        alarm.set_snooze_minutes(settings.snooze_minutes);
+    }
 
-        button_poll::spawn_after(50_u32.millis().into()).unwrap();
+    // Let the buzzer follow the alarm state machine
+    fn alarm_action(action: Action, player: &mut melody::Player, buzzer: &mut buzzer::Buzzer) {
+        match action {
+            Action::Ring => {
+                if player.start(melody::ALARM, true) && melody_task::spawn().is_err() {
+                    player.spawn_failed();
+                }
+            }
+            Action::Silence => {
+                player.stop();
+                buzzer.stop_tone();
+            }
+            Action::None => {}
+        }
     }
 
-    // Buzzer beep task
-    #[task(local = [buzzer])]
-    fn beep_task(cx: beep_task::Context) {
-        cx.local.buzzer.beep(2);
-        beep_task::spawn_after(5000_u32.millis().into()).unwrap();
+    // Play the next note of a melody and schedule the one after
+    #[task(shared = [player])]
+    fn melody_task(mut cx: melody_task::Context) {
+        if let Some(note) = cx.shared.player.lock(|player| player.next_note()) {
+            if note.frequency_hz > 0 {
+                // leave a short gap to separate repeated notes
+                play_tone::spawn(note.frequency_hz, note.duration_ms * 9 / 10).ok();
+            }
+            melody_task::spawn_after(note.duration_ms.millis().into()).unwrap();
+        }
     }
 
     
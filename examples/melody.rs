@@ -0,0 +1,98 @@
+#![no_std]
+
+use command_parser::{rtttl, MELODY_LEN};
+use heapless::String;
+
+/// Built-in melodies, kept in flash
+pub const MELODIES: [&str; 4] = [
+    "alarm:d=8,o=6,b=180:c,p,c,p,c,p,4p",
+    "nokia:d=4,o=5,b=225:8e6,8d6,f#,g#,8c#6,8b,d,e,8b,8a,c#,e,2a",
+    "tetris:d=4,o=5,b=160:e6,8b,8c6,8d6,16e6,16d6,8c6,8b,a,8a,8c6,e6,8d6,8c6,b,8b,8c6,d6,e6,c6,a,2a",
+    "mario:d=4,o=5,b=100:16e6,16e6,32p,8e6,16c6,8e6,8g6,8p,8g,8p",
+];
+
+/// Melody played by alarms
+pub const ALARM: &str = MELODIES[0];
+
+/// Look up a built-in melody by name
+pub fn find(name: &str) -> Option<&'static str> {
+    MELODIES
+        .iter()
+        .copied()
+        .find(|melody| rtttl::parse(melody).is_ok_and(|rtttl| rtttl.name == name))
+}
+
+/// Steps through the notes of a melody, one note at a time, the timing is
+/// left to an RTIC task
+pub struct Player {
+    melody: String<MELODY_LEN>,
+    next: usize,
+    repeat: bool,
+    playing: bool,
+    // a task is scheduled to play the next note
+    scheduled: bool,
+}
+
+impl Player {
+    pub fn new() -> Self {
+        Player {
+            melody: String::new(),
+            next: 0,
+            repeat: false,
+            playing: false,
+            scheduled: false,
+        }
+    }
+
+    /// Start playing `melody` from the beginning, returns true if the
+    /// note task has to be spawned, false if it is already scheduled
+    /// (or the melody is invalid)
+    pub fn start(&mut self, melody: &str, repeat: bool) -> bool {
+        if rtttl::parse(melody).is_err() {
+            return false;
+        }
+        self.melody.clear();
+        if self.melody.push_str(melody).is_err() {
+            return false;
+        }
+        self.next = 0;
+        self.repeat = repeat;
+        self.playing = true;
+        !core::mem::replace(&mut self.scheduled, true)
+    }
+
+    pub fn stop(&mut self) {
+        self.playing = false;
+    }
+
+    /// The note task could not be spawned after `start`, stop so that the
+    /// next `start` spawns it again
+    pub fn spawn_failed(&mut self) {
+        self.playing = false;
+        self.scheduled = false;
+    }
+
+    /// Next note to play, `None` when the melody is over or stopped
+    pub fn next_note(&mut self) -> Option<rtttl::Note> {
+        let note = if self.playing {
+            let rtttl = rtttl::parse(&self.melody).ok()?;
+            match rtttl.notes().nth(self.next) {
+                None if self.repeat => {
+                    self.next = 0;
+                    rtttl.notes().next()
+                }
+                note => note,
+            }
+        } else {
+            None
+        };
+        match note {
+            Some(_) => self.next += 1,
+            None => {
+                self.playing = false;
+                self.scheduled = false;
+            }
+        }
+        note
+    }
+}
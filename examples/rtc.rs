@@ -1,17 +1,23 @@
 #![no_std]
 
-use nrf52833_hal as hal;
+use crate::hal;
 use hal::pac::{self, RTC0};
 use hal::rtc::{Rtc, RtcInterrupt};
 use cortex_m::asm;
 use cortex_m::peripheral::NVIC;
 use rtt_target::{rprintln, rtt_init_print};
 use hal::clocks::{Clocks, ExternalOscillator, Internal, LfOscStarted};
+use clock_core::alarm::{Alarm, MAX_ALARMS};
+use clock_core::calendar::DateTime;
 
 pub struct RTC {
     rtc: Rtc<RTC0>,
     next_compare: u32,
+    // Unix epoch seconds, UTC
     seconds: u32,
+    // local time offset from UTC in minutes
+    tz_offset: i16,
+    alarms: [Option<Alarm>; MAX_ALARMS],
 }
 
 impl RTC {
@@ -36,30 +42,135 @@ impl RTC {
         Self {
             rtc,
             next_compare: 8,
-            seconds: 86380,
+            // 2026-01-01 23:59:40
+            seconds: 1_767_311_980,
+            tz_offset: 0,
+            alarms: Default::default(),
         }
     }
 
-    pub fn handle_interrupt(&mut self) {
+    /// Advance the clock one second, returns the index of an alarm that
+    /// is due at the start of this minute
+    pub fn handle_interrupt(&mut self) -> Option<usize> {
         unsafe {
             (*RTC0::ptr()).events_compare[0].write(|w| w.bits(0));
 
             self.seconds = self.seconds.wrapping_add(1);
-            let hrs = (self.seconds / 3600) % 24;
-            let mins = (self.seconds / 60) % 60;
-            let secs = self.seconds % 60;
 
-            rprintln!("Time: {:02}:{:02}:{:02}", hrs, mins, secs);
+            rprintln!("Time: {}", self.get_datetime());
 
             self.next_compare += 8;
             (*RTC0::ptr()).cc[0].write(|w| w.bits(self.next_compare));
         }
+
+        if self.seconds.is_multiple_of(60) {
+            self.due_alarm()
+        } else {
+            None
+        }
+    }
+
+    fn due_alarm(&self) -> Option<usize> {
+        let now = self.get_datetime();
+        let day = 1 << now.weekday().index();
+        self.alarms().find_map(|(i, alarm)| {
+            (alarm.enabled
+                && alarm.weekdays & day != 0
+                && alarm.hour == now.hour
+                && alarm.minute == now.minute)
+                .then_some(i)
+        })
+    }
+
+    /// Store an alarm in the first free slot, returns its index
+    pub fn add_alarm(&mut self, alarm: Alarm) -> Option<usize> {
+        let i = self.alarms.iter().position(Option::is_none)?;
+        rprintln!("Alarm {} set to {:02}:{:02}", i, alarm.hour, alarm.minute);
+        self.alarms[i] = Some(alarm);
+        Some(i)
+    }
+
+    pub fn remove_alarm(&mut self, i: usize) -> bool {
+        matches!(self.alarms.get_mut(i).map(Option::take), Some(Some(_)))
+    }
+
+    pub fn enable_alarm(&mut self, i: usize, enabled: bool) -> bool {
+        match self.alarms.get_mut(i) {
+            Some(Some(alarm)) => {
+                alarm.enabled = enabled;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn alarm(&self, i: usize) -> Option<&Alarm> {
+        self.alarms.get(i)?.as_ref()
+    }
+
+    /// Iterate over the used alarm slots
+    pub fn alarms(&self) -> impl Iterator<Item = (usize, &Alarm)> {
+        self.alarms
+            .iter()
+            .enumerate()
+            .filter_map(|(i, alarm)| Some((i, alarm.as_ref()?)))
+    }
+
+    pub fn alarm_table(&self) -> &[Option<Alarm>; MAX_ALARMS] {
+        &self.alarms
+    }
+
+    pub fn set_alarm_table(&mut self, alarms: [Option<Alarm>; MAX_ALARMS]) {
+        self.alarms = alarms;
+    }
+
+    /// Local date and time
+    pub fn get_datetime(&self) -> DateTime {
+        DateTime::from_epoch(self.seconds.wrapping_add_signed(self.tz_offset as i32 * 60))
+    }
+
+    /// Set the local date and time, in the range of `DateTime::new`
+    pub fn set_datetime(&mut self, datetime: DateTime) {
+        self.seconds = datetime.to_epoch().wrapping_add_signed(-(self.tz_offset as i32) * 60);
+        rprintln!("Time set to {}", datetime);
+    }
+
+    pub fn tz_offset(&self) -> i16 {
+        self.tz_offset
+    }
+
+    /// Set the offset from UTC in minutes, the local time follows
+    pub fn set_tz_offset(&mut self, minutes: i16) {
+        self.tz_offset = minutes;
+    }
+
+    /// Set the time of day, keeping the date, false if out of range
+    pub fn set_time(&mut self, h: u8, m: u8, s: u8) -> bool {
+        let now = self.get_datetime();
+        match DateTime::new(now.year, now.month, now.day, h, m, s) {
+            Some(datetime) => {
+                self.set_datetime(datetime);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Set the date, keeping the time of day, false if out of range (past
+    /// 2106-02-07 06:28:15)
+    pub fn set_date(&mut self, y: u16, mo: u8, d: u8) -> bool {
+        let now = self.get_datetime();
+        match DateTime::new(y, mo, d, now.hour, now.minute, now.second) {
+            Some(datetime) => {
+                self.set_datetime(datetime);
+                true
+            }
+            None => false,
+        }
     }
 
     pub fn get_time(&self) -> (u32, u32, u32) {
-        let hrs = (self.seconds / 3600) % 24;
-        let mins = (self.seconds / 60) % 60;
-        let secs = self.seconds % 60;
-        (hrs, mins, secs)
+        let datetime = self.get_datetime();
+        (datetime.hour as u32, datetime.minute as u32, datetime.second as u32)
     }
 }
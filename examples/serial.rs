@@ -1,7 +1,7 @@
 #![no_std]
 
 use {
-    nrf52833_hal as hal,
+    crate::hal,
     hal::clocks::{Clocks, ExternalOscillator, Internal, LfOscStarted},
     hal::pac::{CLOCK, USBD},
     hal::usbd::{UsbPeripheral, Usbd},
@@ -10,9 +10,18 @@ use {
         class_prelude::UsbBusAllocator,
         device::{UsbDevice, StringDescriptors, UsbDeviceBuilder, UsbVidPid},
     },
-    usb_device::prelude::*,
+    usb_device::{prelude::*, UsbError},
     usbd_serial::{SerialPort, USB_CLASS_CDC},
+    command_parser::{parse_diagnostic, Command, COMMANDS},
+    core::fmt::Write,
+    heapless::Vec,
 };
+
+/// Longest command line accepted, excess input is discarded up to the next newline
+const LINE_LEN: usize = 256;
+/// Number of USB polls to wait for the host to drain the transmit buffer
+const WRITE_RETRIES: u32 = 1000;
+
 pub static mut CLOCKS: Option<Clocks<ExternalOscillator, Internal, LfOscStarted>> = None;
 
 static mut USB_BUS: Option<UsbBusAllocator<Usbd<UsbPeripheral<'static>>>> = None;
@@ -20,6 +29,44 @@ static mut USB_BUS: Option<UsbBusAllocator<Usbd<UsbPeripheral<'static>>>> = None
 pub struct Serial {
     usb_dev: UsbDevice<'static, Usbd<UsbPeripheral<'static>>>,
     serial: SerialPort<'static, Usbd<UsbPeripheral<'static>>>,
+    line: Vec<u8, LINE_LEN>,
+    overflow: bool,
+}
+
+/// Reply channel handed to the command dispatcher, text written here is
+/// sent before the final `OK`
+pub struct Reply<'a> {
+    usb_dev: &'a mut UsbDevice<'static, Usbd<UsbPeripheral<'static>>>,
+    serial: &'a mut SerialPort<'static, Usbd<UsbPeripheral<'static>>>,
+}
+
+impl Reply<'_> {
+    // Returns false if the host stopped reading and the rest was dropped
+    pub fn write(&mut self, mut data: &[u8]) -> bool {
+        let mut retries = WRITE_RETRIES;
+        while !data.is_empty() {
+            match self.serial.write(data) {
+                Ok(count) => data = &data[count..],
+                Err(UsbError::WouldBlock) if retries > 0 => retries -= 1,
+                Err(_) => {
+                    rprintln!("Error writing data");
+                    return false;
+                }
+            }
+            self.usb_dev.poll(&mut [&mut *self.serial]);
+        }
+        true
+    }
+}
+
+impl Write for Reply<'_> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        if self.write(s.as_bytes()) {
+            Ok(())
+        } else {
+            Err(core::fmt::Error)
+        }
+    }
 }
 
 impl Serial {
@@ -60,18 +107,28 @@ impl Serial {
             .unwrap()
             .build();
 
-        Serial { usb_dev, serial }
+        Serial {
+            usb_dev,
+            serial,
+            line: Vec::new(),
+            overflow: false,
+        }
     }
 
-    pub fn poll(&mut self) {
+    /// Poll the USB device and collect received bytes into a line.
+    ///
+    /// Each complete line is parsed by `command_parser::parse_diagnostic` and
+    /// handed to `dispatch` together with the `Reply` channel. `dispatch`
+    /// returns the reason if the command could not be carried out. The
+    /// outcome is replied as `OK` or `ERR <reason>`.
+    pub fn poll(&mut self, mut dispatch: impl FnMut(Command, &mut Reply) -> Result<(), &'static str>) {
         let mut buf = [0u8; 64];
 
         if self.usb_dev.poll(&mut [&mut self.serial]) {
             match self.serial.read(&mut buf) {
                 Ok(count) if count > 0 => {
                     for &byte in &buf[..count] {
-                        rprintln!("Received byte: {}", byte as char);
-                        self.write(&[byte]);
+                        self.receive(byte, &mut dispatch);
                     }
                 }
                 _ => {}
@@ -79,14 +136,96 @@ impl Serial {
         }
     }
 
+    fn receive(
+        &mut self,
+        byte: u8,
+        dispatch: &mut impl FnMut(Command, &mut Reply) -> Result<(), &'static str>,
+    ) {
+        let mut reply = Reply {
+            usb_dev: &mut self.usb_dev,
+            serial: &mut self.serial,
+        };
+        match byte {
+            b'\r' | b'\n' => {
+                reply.write(b"\r\n");
+                if self.overflow {
+                    self.overflow = false;
+                    self.line.clear();
+                    reply.write(b"ERR LineTooLong\r\n");
+                } else if !self.line.is_empty() {
+                    match parse_diagnostic(&self.line) {
+                        Ok(command) => {
+                            rprintln!("Command: {:?}", command);
+                            match dispatch(command, &mut reply) {
+                                Ok(()) => {
+                                    reply.write(b"OK\r\n");
+                                }
+                                Err(reason) => {
+                                    let _ = write!(reply, "ERR {}\r\n", reason);
+                                }
+                            }
+                        }
+                        // the error name for scripts, then the line with the
+                        // offending token underlined and the usage for people
+                        Err(diagnostic) => {
+                            rprintln!("Command: {:?}", diagnostic.error);
+                            let _ = write!(reply, "ERR {:?}\r\n{}", diagnostic.error, diagnostic);
+                            let _ = COMMANDS.write_hint(&mut reply, &diagnostic);
+                        }
+                    }
+                    self.line.clear();
+                }
+            }
+            b'\t' => complete(&mut self.line, &mut reply),
+            // backspace and delete
+            0x08 | 0x7f => {
+                if self.line.pop().is_some() {
+                    reply.write(b"\x08 \x08");
+                }
+            }
+            _ => {
+                reply.write(&[byte]);
+                if self.line.push(byte).is_err() {
+                    self.overflow = true;
+                }
+            }
+        }
+    }
+
     pub fn write(&mut self, data: &[u8]) {
-        match self.serial.write(data) {
-            Ok(_) => {
-                self.usb_dev.poll(&mut [&mut self.serial]);
+        Reply {
+            usb_dev: &mut self.usb_dev,
+            serial: &mut self.serial,
+        }
+        .write(data);
+    }
+}
+
+/// Complete the last word of `line` from the command table, or list the
+/// choices if there are several
+fn complete(line: &mut Vec<u8, LINE_LEN>, reply: &mut Reply) {
+    let Ok(text) = core::str::from_utf8(line) else {
+        return;
+    };
+    let partial = text.rsplit(char::is_whitespace).next().unwrap_or_default().len();
+    let choices = COMMANDS.complete(text);
+    match choices[..] {
+        [] => {}
+        [choice] => {
+            for &byte in choice.as_bytes()[partial..].iter().chain(b" ") {
+                if line.push(byte).is_err() {
+                    break;
+                }
+                reply.write(&[byte]);
             }
-            Err(_) => {
-                rprintln!("Error writing data");
+        }
+        _ => {
+            reply.write(b"\r\n");
+            for choice in &choices {
+                let _ = write!(reply, "{} ", choice);
             }
+            reply.write(b"\r\n");
+            reply.write(line);
         }
     }
 }
@@ -0,0 +1,18 @@
+#![no_std]
+
+use crate::hal;
+use hal::nvmc::Nvmc;
+use hal::pac::NVMC;
+use clock_core::settings::Store;
+
+// Two 4 kB pages at the end of the flash, the program must not grow
+// into them
+const SETTINGS_LEN: usize = 2 * 4096;
+
+pub type SettingsStore = Store<Nvmc<NVMC>>;
+
+pub fn new(nvmc: NVMC) -> SettingsStore {
+    // only accessed through the returned store
+    let storage = unsafe { core::slice::from_raw_parts_mut(hal::SETTINGS_FLASH as *mut u8, SETTINGS_LEN) };
+    Store::new(Nvmc::new(nvmc, storage))
+}
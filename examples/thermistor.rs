@@ -1,37 +1,102 @@
 #![no_std]
 
-use nrf52833_hal::{
-    gpio::{p0::P0_03, Disconnected},
-    saadc::{Saadc, SaadcConfig, Resolution, Oversample},
-};
-use libm::logf;
+use crate::hal::saadc::{InternalVdd, Saadc, SaadcConfig, Resolution, Oversample};
+use crate::board::ThermistorPin;
+use clock_core::sensor::{divider_ohms, Celsius, Filter, FilterConfig, SensorError};
+use clock_core::thermistor::{Model, ThermistorModel};
 
-const THERMISTOR_BETA: f32 = 3950.0;
 const REF_RESISTOR: f32 = 10000.0;
-const TEMP_REF_K: f32 = 298.15;
-const SYS_VOLTAGE: f32 = 2.8;
+// input voltage at the largest reading, the 0.6 V internal reference with
+// the default 1/6 gain
+const FULL_SCALE: f32 = 3.6;
 const ADC_MAX: f32 = 4095.0;
 
 pub struct Thermistor {
     adc: Saadc,
-    sensor_pin: P0_03<Disconnected>,
+    sensor_pin: ThermistorPin,
+    // median of the ADC samples, moving average of the temperatures
+    filter: Filter,
+    // resistance to temperature, fitted by `cal fit`
+    model: Model,
+    // added to readings, in 1/100 °C
+    calibration: i16,
 }
 
 impl Thermistor {
-    pub fn new(saadc: Saadc, sensor_pin: P0_03<Disconnected>) -> Self {
-        Self { adc: saadc, sensor_pin }
+    pub fn new(saadc: Saadc, sensor_pin: ThermistorPin, filter: FilterConfig) -> Self {
+        Self {
+            adc: saadc,
+            sensor_pin,
+            filter: Filter::new(filter),
+            model: Model::default(),
+            calibration: 0,
+        }
     }
 
-    pub fn read_temperature(&mut self) -> f32 {
-        let reading = self.adc.read_channel(&mut self.sensor_pin).unwrap();
-        Self::calculate_temperature(reading)
+    // Filtered temperature, a fault restarts the moving average
+    pub fn read_temperature(&mut self) -> Result<Celsius, SensorError> {
+        match self.read_resistance() {
+            Ok(ohms) => {
+                let celsius = self.celsius(ohms);
+                Ok(self.filter.smooth(celsius))
+            }
+            Err(err) => {
+                self.filter.reset();
+                Err(err)
+            }
+        }
     }
 
-    fn calculate_temperature(adc_val: i16) -> f32 {
-        let voltage = (adc_val as f32) * SYS_VOLTAGE / ADC_MAX;
-        let thermistor_r = REF_RESISTOR * (voltage / (SYS_VOLTAGE - voltage));
-        let log_ratio = logf(thermistor_r / REF_RESISTOR);
-        let inv_temp = (1.0 / TEMP_REF_K) + (log_ratio / THERMISTOR_BETA);
-        (1.0 / inv_temp) - 273.15
+    // Calibrated temperature of a resistance, without the moving average
+    pub fn celsius(&self, ohms: f32) -> Celsius {
+        self.model.celsius(ohms) + self.calibration as f32 / 100.0
+    }
+
+    // Thermistor resistance in Ω from the medians of the ADC samples of the
+    // divider and of VDD, which feeds the divider, so the result does not
+    // depend on the supply voltage. The calibration points are taken in Ω
+    // so that a fit does not depend on the current model.
+    pub fn read_resistance(&mut self) -> Result<f32, SensorError> {
+        let (adc, pin) = (&mut self.adc, &mut self.sensor_pin);
+        let reading = self
+            .filter
+            .median(|| adc.read_channel(pin).map_err(|_| SensorError::AdcError))?;
+        let supply = self.read_supply()?;
+        divider_ohms(reading, supply, REF_RESISTOR)
+    }
+
+    // Supply voltage in V
+    pub fn read_vdd(&mut self) -> Result<f32, SensorError> {
+        Ok(self.read_supply()? as f32 * FULL_SCALE / ADC_MAX)
+    }
+
+    fn read_supply(&mut self) -> Result<i16, SensorError> {
+        let adc = &mut self.adc;
+        self.filter
+            .median(|| adc.read_channel(&mut InternalVdd).map_err(|_| SensorError::AdcError))
+    }
+
+    pub fn filter(&self) -> FilterConfig {
+        self.filter.config()
+    }
+
+    pub fn set_filter(&mut self, config: FilterConfig) {
+        self.filter = Filter::new(config);
+    }
+
+    pub fn model(&self) -> Model {
+        self.model
+    }
+
+    pub fn set_model(&mut self, model: Model) {
+        self.model = model;
+    }
+
+    pub fn calibration(&self) -> i16 {
+        self.calibration
+    }
+
+    pub fn set_calibration(&mut self, centi_celsius: i16) {
+        self.calibration = centi_celsius;
     }
 }
@@ -0,0 +1,6 @@
+/* Keep the firmware image out of the settings pages, the last two 4 kB
+   pages of the flash, see `SETTINGS_FLASH` in examples/hal.rs */
+_settings_flash = ORIGIN(FLASH) + LENGTH(FLASH) - 2 * 4096;
+
+ASSERT(__sidata + SIZEOF(.data) <= _settings_flash && __veneer_limit <= _settings_flash, "
+ERROR(settings.x): the firmware overlaps the settings pages at the end of the flash");